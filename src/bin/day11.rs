@@ -5,13 +5,13 @@ use std::num::NonZeroUsize;
 fn main() {
     let mut args = env::args();
     match args.nth(1) {
-        Some(path) => match Grid::from_path(path) {
-            Ok(grid) => {
-                let galaxies_part1 = expanded_universe(&grid, NonZeroUsize::new(2).unwrap());
-                println!("{}", galaxies_part1.sum_manhattan_distances());
-                let galaxies_part2 =
-                    expanded_universe(&grid, NonZeroUsize::new(1_000_000).unwrap());
-                println!("{}", galaxies_part2.sum_manhattan_distances());
+        Some(path) => match Universe::from_path(path) {
+            Ok(universe) => {
+                println!("{}", universe.sum_distances(NonZeroUsize::new(2).unwrap()));
+                println!(
+                    "{}",
+                    universe.sum_distances(NonZeroUsize::new(1_000_000).unwrap())
+                );
             }
             Err(e) => println!("{:#?}", e),
         },
@@ -1,21 +1,19 @@
 use aoc_2023::day12::*;
 use std::env;
+use std::num::NonZeroUsize;
 
 fn main() {
     let mut args = env::args();
     match args.nth(1) {
         Some(path) => match rows_from_path(path) {
             Ok(rows) => {
-                let mut analyzers: Vec<_> = rows.into_iter().map(RowAnalyzer::from).collect();
-                let sum = analyzers
-                    .iter_mut()
-                    .map(|x| x.count_arrangements())
-                    .sum::<usize>();
+                let sum = count_arrangements_cached(&rows, |row| {
+                    RowAnalyzer::from(row.clone()).count_arrangements()
+                })
+                .into_iter()
+                .sum::<u64>();
                 println!("{}", sum);
-                let sum = analyzers
-                    .iter_mut()
-                    .map(|x| x.count_arrangements_with_unfold())
-                    .sum::<usize>();
+                let sum = sum_arrangements_parallel(&rows, NonZeroUsize::new(5).unwrap());
                 println!("{}", sum);
             }
             Err(e) => println!("{:#?}", e),
@@ -0,0 +1,16 @@
+use aoc_2023::day17::*;
+use std::env;
+
+fn main() {
+    let mut args = env::args();
+    match args.nth(1) {
+        Some(path) => match City::from_path(path) {
+            Ok(city) => {
+                println!("{}", city.minimal_heat_loss());
+                println!("{}", city.minimal_heat_loss_ultra());
+            }
+            Err(e) => println!("{:#?}", e),
+        },
+        None => println!("Please provide path to file as first argument"),
+    }
+}
@@ -4,16 +4,13 @@ use std::env;
 fn main() {
     let mut args = env::args();
     match args.nth(1) {
-        Some(path) => {
-            match sum_schematic(&path) {
-                Ok(sum) => println!("{}", sum),
-                Err(e) => println!("{:#?}", e),
+        Some(path) => match schematic_summary(path) {
+            Ok((sum, gear_sum)) => {
+                println!("{}", sum);
+                println!("{}", gear_sum);
             }
-            match gear_sum(path) {
-                Ok(sum) => println!("{}", sum),
-                Err(e) => println!("{:#?}", e),
-            }
-        }
+            Err(e) => println!("{:#?}", e),
+        },
         None => println!("Please provide a path as the first argument"),
     }
 }
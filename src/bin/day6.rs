@@ -7,8 +7,7 @@ fn main() {
         Some(path) => {
             match races_from_path_part1(&path) {
                 Ok(x) => {
-                    let n: u64 = x.iter().map(|r| r.ways_to_win_bracketing()).product();
-                    println!("{}", n);
+                    println!("{}", product_of_ways(&x));
                 }
                 Err(e) => println!("{:#?}", e),
             }
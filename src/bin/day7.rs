@@ -1,13 +1,17 @@
 use aoc_2023::day7::*;
-use std::env;
+use std::{env, fs};
 
 fn main() {
     let mut args = env::args();
     match args.nth(1) {
-        Some(path) => match hand_bids_from_path(path) {
-            Ok(mut x) => {
-                let n = total_winnings(&mut x);
-                println!("{}", n);
+        Some(path) => match fs::read_to_string(path) {
+            Ok(s) => {
+                for rules in [Rules::Standard, Rules::Joker] {
+                    match solve_from_str(&s, rules) {
+                        Ok(n) => println!("{}", n),
+                        Err(e) => println!("{:#?}", e),
+                    }
+                }
             }
             Err(e) => println!("{:#?}", e),
         },
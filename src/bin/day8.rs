@@ -12,7 +12,7 @@ fn main() {
                     Ok(n) => println!("terminate at exit after: {}", n),
                     Err(n) => println!("does not terminate at exit after: {}", n),
                 }
-                match network.simultaneous_traverse(seq) {
+                match network.simultaneous_traverse_crt(seq) {
                     Ok(n) => println!("terminate at exit after: {}", n),
                     Err(n) => println!("does not terminate at exit after: {}", n),
                 }
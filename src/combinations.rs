@@ -28,21 +28,29 @@ pub fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
     }
     v
 }
-/// Works for `n <= 62` and any `k`.
+/// Panics if the result does not fit in a `u64`; see `checked_binomial`.
 pub fn binomial(n: u64, k: u64) -> u64 {
+    checked_binomial(n, k).expect("binomial coefficient overflows u64")
+}
+
+/// The binomial coefficient, or `None` if it does not fit in a `u64`.
+/// Intermediate products are formed in `u128`, hence, this is exact for
+/// any `n` and `k` such that the result fits.
+pub fn checked_binomial(n: u64, k: u64) -> Option<u64> {
     if n < k {
-        0
+        Some(0)
     } else {
         let m = n - k;
-        let (mut m, k) = if k < m { (m, k) } else { (k, m) };
-        let mut p = 1;
-        let mut i: u64 = 0;
+        let (m, k) = if k < m { (m, k) } else { (k, m) };
+        let (mut m, k) = (m as u128, k as u128);
+        let mut p: u128 = 1;
+        let mut i: u128 = 0;
         while i < k {
             i += 1;
             m += 1;
-            p = m * p / i;
+            p = u64::try_from(m.checked_mul(p)? / i).ok()? as u128;
         }
-        p
+        Some(p as u64)
     }
 }
 
@@ -225,6 +233,42 @@ impl Iterator for Combinations {
     fn next(&mut self) -> Option<Self::Item> {
         self.next_combination()
     }
+    /// Jump directly to the combination `n` places ahead using `combinatorial_index`,
+    /// rather than advancing `n` times. If the number of combinations does not fit
+    /// in a `usize`, this falls back to advancing `n` times.
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        let total = checked_binomial(self.n as u64, self.k as u64)
+            .and_then(|total| usize::try_from(total).ok());
+        let Some(total) = total else {
+            for _ in 0..n {
+                if self.is_done() {
+                    break;
+                }
+                self.next_combination_mut();
+            }
+            return self.next_combination();
+        };
+        let target = self.linear_index()?.checked_add(n).filter(|l| *l < total);
+        match target {
+            Some(l) => {
+                self.digits = self.combinatorial_index(l)?;
+                self.next_combination()
+            }
+            None => {
+                // Exhaust the iterator by moving past the last combination.
+                self.digits = self.combinatorial_index(total.checked_sub(1)?)?;
+                self.next_combination_mut();
+                None
+            }
+        }
+    }
+}
+
+/// The `k`-subsets of `items`, in the same order as `Combinations`, each as references
+/// to the chosen elements.
+pub fn subsets<T>(items: &[T], k: usize) -> impl Iterator<Item = Vec<&T>> {
+    Combinations::new(items.len(), k)
+        .map(move |digits| digits.into_iter().map(|i| &items[i]).collect())
 }
 
 #[cfg(test)]
@@ -242,6 +286,17 @@ mod tests {
         assert_eq!(binomial(50, 11), 37353738800);
         assert_eq!(binomial(61, 30), 232714176627630544);
         assert_eq!(binomial(62, 31), 465428353255261088);
+        assert_eq!(binomial(67, 33), 14226520737620288370);
+    }
+
+    #[test]
+    fn checked_binomial_works() {
+        assert_eq!(checked_binomial(3, 4), Some(0));
+        assert_eq!(checked_binomial(62, 31), Some(465428353255261088));
+        assert_eq!(checked_binomial(67, 33), Some(14226520737620288370));
+        assert_eq!(checked_binomial(68, 34), None);
+        assert_eq!(checked_binomial(100, 50), None);
+        assert_eq!(checked_binomial(100, 1), Some(100));
     }
 
     #[test]
@@ -283,6 +338,78 @@ mod tests {
         }
     }
     #[test]
+    fn nth() {
+        let mut x = Combinations::new(20, 5);
+        let mut y = x.clone();
+        for _ in 0..1000 {
+            y.next_combination_mut();
+        }
+        assert_eq!(x.nth(1000), y.next());
+        assert_eq!(x.digits, y.digits);
+        assert_eq!(x.next(), y.next());
+
+        for n in 0..8 {
+            for k in 0..n + 1 {
+                let end = binomial(n as u64, k as u64) as usize;
+                for m in 0..end + 2 {
+                    let mut x = Combinations::new(n, k);
+                    let mut y = x.clone();
+                    for _ in 0..m {
+                        y.next();
+                    }
+                    assert_eq!(x.nth(m), y.next(), "n: {}, k: {}, m: {}", n, k, m);
+                    assert_eq!(x.count_remaining(), y.count_remaining());
+                    // Relative to the current position.
+                    assert_eq!(x.nth(1), y.nth(1));
+                    assert_eq!(x.next(), y.next());
+                }
+            }
+        }
+        let mut x = Combinations::new(3, 5);
+        assert_eq!(x.nth(2), None);
+
+        // Far beyond what could be reached by stepping.
+        let mut x = Combinations::new(60, 30);
+        let last = binomial(60, 30) as usize - 1;
+        assert_eq!(x.nth(last), Some((30..60).collect()));
+        assert_eq!(x.next(), None);
+
+        // The number of combinations overflows, hence, `nth` must step.
+        let mut x = Combinations::new(100, 50);
+        let mut y = x.clone();
+        // Exercise the override itself, rather than `next`.
+        #[allow(clippy::iter_nth_zero)]
+        let first = x.nth(0);
+        assert_eq!(first, Some((0..50).collect()));
+        for _ in 0..1000 {
+            y.next_combination_mut();
+        }
+        assert_eq!(x.nth(999), y.next());
+        assert_eq!(x.digits, y.digits);
+        let mut x = Combinations::new(100, 50).skip(3);
+        let mut y = Combinations::new(100, 50);
+        for _ in 0..3 {
+            y.next_combination_mut();
+        }
+        assert_eq!(x.next(), y.next());
+    }
+
+    #[test]
+    fn subsets_works() {
+        let items = ['a', 'b', 'c'];
+        let x: Vec<_> = subsets(&items, 2).collect();
+        assert_eq!(
+            x,
+            vec![vec![&'a', &'b'], vec![&'a', &'c'], vec![&'b', &'c']]
+        );
+        assert_eq!(
+            subsets(&items, 0).collect::<Vec<_>>(),
+            vec![Vec::<&char>::new()]
+        );
+        assert_eq!(subsets(&items, 4).count(), 0);
+        assert_eq!(subsets(&[1, 2, 3, 4, 5, 6, 7], 3).count(), 35);
+    }
+    #[test]
     fn count_remaining() {
         let mut x = Combinations::new(7, 4);
         // Many specific checks
@@ -31,16 +31,65 @@ where
     Ok(sum)
 }
 
+/// As `parse_lines`, but a line which contains no calibration value is an error
+/// (of kind `InvalidData`) rather than a contribution of `0`.
+pub fn parse_lines_strict<F, R: BufRead>(f: F, mut r: R) -> io::Result<u64>
+where
+    F: Fn(&str) -> Option<u8>,
+{
+    let mut s = String::with_capacity(1024);
+    let mut sum: u64 = 0;
+    let mut line_num: usize = 1;
+    while r.read_line(&mut s)? != 0 {
+        match f(&s) {
+            Some(x) => sum += x as u64,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {} has no calibration value: {:?}", line_num, s),
+                ))
+            }
+        }
+        line_num += 1;
+        s.clear();
+    }
+    Ok(sum)
+}
+
+/// Equivalent to `part1::parse_line`; for lines known to contain no spelled digits.
+pub fn parse_line_digits_only(line: &str) -> u8 {
+    part1::parse_line(line)
+}
+
+/// A line without alphabetic characters cannot contain a spelled digit,
+/// hence, the digit-only parser agrees with `part2::parse_line` on it.
+fn is_digits_only(line: &str) -> bool {
+    !line.bytes().any(|b| b.is_ascii_alphabetic())
+}
+
+/// As `part2::parse_line`, but dispatching to the cheaper `parse_line_digits_only`
+/// if the line contains no alphabetic characters.
+pub fn parse_line_auto(line: &str) -> u8 {
+    if is_digits_only(line) {
+        parse_line_digits_only(line)
+    } else {
+        part2::parse_line(line)
+    }
+}
+
 pub mod part1 {
     fn decimal(c: char) -> Option<u8> {
         c.to_digit(10).map(|d| d as u8)
     }
     pub fn parse_line(line: &str) -> u8 {
+        try_parse_line(line).unwrap_or(0)
+    }
+    pub fn try_parse_line(line: &str) -> Option<u8> {
         let mut iter = line.chars().filter(|c| c.is_ascii_digit());
         match (iter.next().and_then(decimal), iter.last().and_then(decimal)) {
-            (Some(d_1), Some(d_0)) => d_1 * 10 + d_0,
-            (Some(d_0), None) => d_0 * 10 + d_0,
-            _ => 0,
+            (Some(d_1), Some(d_0)) => Some(d_1 * 10 + d_0),
+            (Some(d_0), None) => Some(d_0 * 10 + d_0),
+            _ => None,
         }
     }
 
@@ -70,6 +119,25 @@ pub mod part1 {
             assert_eq!(parse_line2("43two6eight9"), 49);
         }
 
+        #[test]
+        fn try_parse_line_works() {
+            assert_eq!(try_parse_line("abc"), None);
+            assert_eq!(try_parse_line("a1b"), Some(11));
+            assert_eq!(parse_line("abc"), 0);
+        }
+
+        #[test]
+        fn parse_lines_strict_works() {
+            let s = "1abc2\npqr3stu8vwx";
+            assert_eq!(
+                parse_lines_strict(try_parse_line, BufReader::new(s.as_bytes())).unwrap(),
+                50
+            );
+            let s = "1abc2\npqrstuvwx\na1b2c3d4e5f";
+            let e = parse_lines_strict(try_parse_line, BufReader::new(s.as_bytes())).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        }
+
         #[test]
         fn parse_lines_works() {
             let s = "1abc2
@@ -294,14 +362,43 @@ pub mod part2 {
     }
 
     pub fn parse_line(line: &str) -> u8 {
+        try_parse_line(line).unwrap_or(0)
+    }
+    pub fn try_parse_line(line: &str) -> Option<u8> {
         let first = first(line);
         let last = last(line);
         match (first, last) {
-            (Some(d_1), Some(d_0)) => d_1 * 10 + d_0,
-            (Some(d_0), None) => d_0 * 10 + d_0,
-            _ => 0,
+            (Some(d_1), Some(d_0)) => Some(d_1 * 10 + d_0),
+            (Some(d_0), None) => Some(d_0 * 10 + d_0),
+            _ => None,
+        }
+    }
+
+    const WORDS: [&str; 10] = [
+        "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+    ];
+    /// The digit (numeric or spelled) which begins at byte offset `i`, if any.
+    fn digit_at(line: &str, i: usize) -> Option<u8> {
+        let rest = &line.as_bytes()[i..];
+        match rest.first() {
+            Some(b) if b.is_ascii_digit() => Some(b - b'0'),
+            _ => WORDS
+                .iter()
+                .position(|w| rest.starts_with(w.as_bytes()))
+                .map(|d| d as u8),
         }
     }
+    /// The byte offset and value of the first calibration digit, for diagnostics;
+    /// the value agrees with that used by `parse_line`.
+    pub fn first_digit_with_pos(line: &str) -> Option<(usize, u8)> {
+        (0..line.len()).find_map(|i| digit_at(line, i).map(|d| (i, d)))
+    }
+    /// As `first_digit_with_pos`, but for the last calibration digit.
+    pub fn last_digit_with_pos(line: &str) -> Option<(usize, u8)> {
+        (0..line.len())
+            .rev()
+            .find_map(|i| digit_at(line, i).map(|d| (i, d)))
+    }
 
     #[cfg(test)]
     mod tests {
@@ -339,6 +436,26 @@ pub mod part2 {
             assert_eq!(parse_line("threekp1onefrfjbrmmpmsdsvfour"), 34);
         }
 
+        #[test]
+        fn digit_with_pos() {
+            assert_eq!(first_digit_with_pos("eightwothree"), Some((0, 8)));
+            assert_eq!(last_digit_with_pos("eightwothree"), Some((7, 3)));
+            assert_eq!(first_digit_with_pos("xtwone3four"), Some((1, 2)));
+            assert_eq!(last_digit_with_pos("xtwone3four"), Some((7, 4)));
+            assert_eq!(last_digit_with_pos("twone"), Some((2, 1)));
+            assert_eq!(first_digit_with_pos("abc"), None);
+            assert_eq!(last_digit_with_pos(""), None);
+        }
+
+        #[test]
+        fn overlapping_words() {
+            assert_eq!(parse_line("eighthree"), 83);
+            assert_eq!(parse_line("sevenine"), 79);
+            assert_eq!(parse_line("twone"), 21);
+            assert_eq!(parse_line("eightwo"), 82);
+            assert_eq!(parse_line("oneight"), 18);
+        }
+
         #[test]
         fn parse_lines_works() {
             let s = "two1nine
@@ -358,7 +475,8 @@ zoneight234
 
 // This is far too tedious without metaprogramming.
 // N.B. negation on unsigned integers is not a robust hash function.
-// I would expect problems if the character set were not limited to ASCII.
+// I would expect problems if the character set were not limited to ASCII;
+// even within ASCII, collisions occur, hence, every match is verified.
 pub mod part2_alt {
 
     const OFFSET: u32 = '0' as u32;
@@ -490,6 +608,65 @@ pub mod part2_alt {
         }
     }
 
+    // The hashes of prefixes (and suffixes) collide, e.g. "nq" and "ze", hence,
+    // a window may only be retained if it is actually the beginning (end) of a word.
+    fn is_prefix2(c_0: u32, c_1: u32) -> bool {
+        check_seq!(c_0, c_1 ; 'z', 'e')
+            || check_seq!(c_0, c_1 ; 'o', 'n')
+            || check_seq!(c_0, c_1 ; 't', 'w')
+            || check_seq!(c_0, c_1 ; 't', 'h')
+            || check_seq!(c_0, c_1 ; 'f', 'o')
+            || check_seq!(c_0, c_1 ; 'f', 'i')
+            || check_seq!(c_0, c_1 ; 's', 'i')
+            || check_seq!(c_0, c_1 ; 's', 'e')
+            || check_seq!(c_0, c_1 ; 'e', 'i')
+            || check_seq!(c_0, c_1 ; 'n', 'i')
+    }
+
+    fn is_prefix3(c_0: u32, c_1: u32, c_2: u32) -> bool {
+        check_seq!(c_0, c_1, c_2 ; 'z', 'e', 'r')
+            || check_seq!(c_0, c_1, c_2 ; 't', 'h', 'r')
+            || check_seq!(c_0, c_1, c_2 ; 'f', 'o', 'u')
+            || check_seq!(c_0, c_1, c_2 ; 'f', 'i', 'v')
+            || check_seq!(c_0, c_1, c_2 ; 's', 'e', 'v')
+            || check_seq!(c_0, c_1, c_2 ; 'e', 'i', 'g')
+            || check_seq!(c_0, c_1, c_2 ; 'n', 'i', 'n')
+    }
+
+    fn is_prefix4(c_0: u32, c_1: u32, c_2: u32, c_3: u32) -> bool {
+        check_seq!(c_0, c_1, c_2, c_3 ; 't', 'h', 'r', 'e')
+            || check_seq!(c_0, c_1, c_2, c_3 ; 's', 'e', 'v', 'e')
+            || check_seq!(c_0, c_1, c_2, c_3 ; 'e', 'i', 'g', 'h')
+    }
+
+    fn is_suffix2(c_3: u32, c_4: u32) -> bool {
+        check_seq!(c_3, c_4 ; 'r', 'o')
+            || check_seq!(c_3, c_4 ; 'w', 'o')
+            || check_seq!(c_3, c_4 ; 'n', 'e')
+            || check_seq!(c_3, c_4 ; 'e', 'e')
+            || check_seq!(c_3, c_4 ; 'u', 'r')
+            || check_seq!(c_3, c_4 ; 'v', 'e')
+            || check_seq!(c_3, c_4 ; 'i', 'x')
+            || check_seq!(c_3, c_4 ; 'e', 'n')
+            || check_seq!(c_3, c_4 ; 'h', 't')
+    }
+
+    fn is_suffix3(c_2: u32, c_3: u32, c_4: u32) -> bool {
+        check_seq!(c_2, c_3, c_4 ; 'e', 'r', 'o')
+            || check_seq!(c_2, c_3, c_4 ; 'r', 'e', 'e')
+            || check_seq!(c_2, c_3, c_4 ; 'o', 'u', 'r')
+            || check_seq!(c_2, c_3, c_4 ; 'i', 'v', 'e')
+            || check_seq!(c_2, c_3, c_4 ; 'v', 'e', 'n')
+            || check_seq!(c_2, c_3, c_4 ; 'g', 'h', 't')
+            || check_seq!(c_2, c_3, c_4 ; 'i', 'n', 'e')
+    }
+
+    fn is_suffix4(c_1: u32, c_2: u32, c_3: u32, c_4: u32) -> bool {
+        check_seq!(c_1, c_2, c_3, c_4 ; 'h', 'r', 'e', 'e')
+            || check_seq!(c_1, c_2, c_3, c_4 ; 'e', 'v', 'e', 'n')
+            || check_seq!(c_1, c_2, c_3, c_4 ; 'i', 'g', 'h', 't')
+    }
+
     macro_rules! is_hash_eq {
         ($h:ident ; $($rhs:ident),+) => {
             $( ($h == $rhs) )|+
@@ -566,7 +743,7 @@ pub mod part2_alt {
                         }
                     } else if j == 4 {
                         // Same concept, but `break` on `THRE`, `SEVE`, `EIGH`
-                        if is_hash_eq!(h ; THRE, SEVE, EIGH) {
+                        if is_hash_eq!(h ; THRE, SEVE, EIGH) && is_prefix4(c_0, c_1, c_2, c_3) {
                             break;
                         } else if is_hash_eq!(h ; ZERO, FOUR, FIVE, NINE) {
                             if let Some(x) = check4(c_0, c_1, c_2, c_3) {
@@ -589,7 +766,9 @@ pub mod part2_alt {
                         }
                     } else if j == 3 {
                         // Same concept as j == 4
-                        if is_hash_eq!(h ; ZER, THR, FOU, FIV, SEV, EIG, NIN) {
+                        if is_hash_eq!(h ; ZER, THR, FOU, FIV, SEV, EIG, NIN)
+                            && is_prefix3(c_0, c_1, c_2)
+                        {
                             break;
                         } else if is_hash_eq!(h ; ONE, TWO, SIX) {
                             if let Some(x) = check3(c_0, c_1, c_2) {
@@ -609,7 +788,9 @@ pub mod part2_alt {
                             j -= 1;
                         }
                     } else if j == 2 {
-                        if is_hash_eq!(h ; ZE, ON, TW, TH, FO, FI, SI, SE, EI, NI) {
+                        if is_hash_eq!(h ; ZE, ON, TW, TH, FO, FI, SI, SE, EI, NI)
+                            && is_prefix2(c_0, c_1)
+                        {
                             break;
                         } else {
                             h += c_0;
@@ -701,7 +882,7 @@ pub mod part2_alt {
                             j -= 1;
                         }
                     } else if j == 4 {
-                        if is_hash_eq!(h ; HREE, EVEN, IGHT) {
+                        if is_hash_eq!(h ; HREE, EVEN, IGHT) && is_suffix4(c_1, c_2, c_3, c_4) {
                             break;
                         } else if is_hash_eq!(h ; ZERO, FOUR, FIVE, NINE) {
                             if let Some(x) = check4(c_1, c_2, c_3, c_4) {
@@ -724,7 +905,9 @@ pub mod part2_alt {
                         }
                     } else if j == 3 {
                         // Same concept as j == 4
-                        if is_hash_eq!(h ; ERO, REE, OUR, IVE, VEN, GHT, INE) {
+                        if is_hash_eq!(h ; ERO, REE, OUR, IVE, VEN, GHT, INE)
+                            && is_suffix3(c_2, c_3, c_4)
+                        {
                             break;
                         } else if is_hash_eq!(h ; ONE, TWO, SIX) {
                             if let Some(x) = check3(c_2, c_3, c_4) {
@@ -744,7 +927,9 @@ pub mod part2_alt {
                             j -= 1;
                         }
                     } else if j == 2 {
-                        if is_hash_eq!(h ; RO, WO, NE, EE, UR, VE, IX, EN, HT) {
+                        if is_hash_eq!(h ; RO, WO, NE, EE, UR, VE, IX, EN, HT)
+                            && is_suffix2(c_3, c_4)
+                        {
                             break;
                         } else {
                             h += c_4;
@@ -768,12 +953,15 @@ pub mod part2_alt {
     }
 
     pub fn parse_line(line: &str) -> u8 {
+        try_parse_line(line).unwrap_or(0)
+    }
+    pub fn try_parse_line(line: &str) -> Option<u8> {
         let first = first(line);
         let last = last(line);
         match (first, last) {
-            (Some(d_1), Some(d_0)) => d_1 as u8 * 10 + d_0 as u8,
-            (Some(d_0), None) => d_0 as u8 * 10 + d_0 as u8,
-            _ => 0,
+            (Some(d_1), Some(d_0)) => Some(d_1 as u8 * 10 + d_0 as u8),
+            (Some(d_0), None) => Some(d_0 as u8 * 10 + d_0 as u8),
+            _ => None,
         }
     }
 
@@ -877,6 +1065,62 @@ pub mod part2_alt {
             assert_eq!(parse_line("threekp1onefrfjbrmmpmsdsvfour"), 34);
         }
 
+        #[test]
+        fn overlapping_words() {
+            assert_eq!(parse_line("eighthree"), 83);
+            assert_eq!(parse_line("sevenine"), 79);
+            assert_eq!(parse_line("twone"), 21);
+            assert_eq!(parse_line("eightwo"), 82);
+            assert_eq!(parse_line("oneight"), 18);
+        }
+
+        #[test]
+        fn hash_collisions() {
+            // "nq" collides with "ze", "nqo" and "qon" with "thr", "qone" with "thre"
+            assert_eq!(parse_line("nqone"), 11);
+            assert_eq!(parse_line("venqone"), 11);
+            assert_eq!(parse_line("enqonefive"), 15);
+            assert_eq!(parse_line("enqonetwo4seven"), 17);
+        }
+
+        #[test]
+        fn agrees_with_part2() {
+            const WORDS: [&str; 10] = [
+                "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+            ];
+            // Each word, along with its proper prefixes and suffixes.
+            let mut pieces: Vec<&str> = Vec::new();
+            for w in WORDS {
+                for k in 1..w.len() {
+                    pieces.push(&w[..k]);
+                    pieces.push(&w[k..]);
+                }
+                pieces.push(w);
+            }
+            pieces.sort_unstable();
+            pieces.dedup();
+            let mut s = String::new();
+            for a in pieces.iter() {
+                for b in pieces.iter() {
+                    s.clear();
+                    s.push_str(a);
+                    s.push_str(b);
+                    assert_eq!(parse_line(&s), part2::parse_line(&s), "{}", s);
+                }
+            }
+            for a in WORDS {
+                for b in WORDS {
+                    for c in WORDS {
+                        s.clear();
+                        s.push_str(a);
+                        s.push_str(b);
+                        s.push_str(c);
+                        assert_eq!(parse_line(&s), part2::parse_line(&s), "{}", s);
+                    }
+                }
+            }
+        }
+
         #[test]
         fn parse_lines_works() {
             let s = "two1nine
@@ -893,3 +1137,87 @@ zoneight234
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::XorShift;
+
+    const WORDS: [&str; 10] = [
+        "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+    ];
+    // Letters which occur in the words are more likely to produce near-misses.
+    const FILLER: &[u8] = b"abcdefghijklmnopqrstuvwxyzeinorstw";
+
+    /// Generate a string of `n` pieces, each a spelled digit, a suffix or prefix
+    /// of a spelled digit, an ASCII digit or a filler letter.
+    fn generate(rng: &mut XorShift, n: usize) -> String {
+        let mut s = String::new();
+        for _ in 0..n {
+            let w = WORDS[rng.below(WORDS.len())];
+            match rng.below(5) {
+                0 => s.push_str(w),
+                1 => s.push_str(&w[rng.below(w.len())..]),
+                2 => s.push_str(&w[..1 + rng.below(w.len() - 1)]),
+                // Digits end the search, so should be comparatively rare.
+                3 if rng.below(4) == 0 => s.push(char::from(b'0' + rng.below(10) as u8)),
+                _ => s.push(char::from(FILLER[rng.below(FILLER.len())])),
+            }
+        }
+        s
+    }
+
+    #[test]
+    fn parse_line_auto() {
+        assert!(super::is_digits_only("24"));
+        assert!(super::is_digits_only("2-4\n"));
+        assert_eq!(super::parse_line_auto("24"), 24);
+        assert!(!super::is_digits_only("two1nine"));
+        assert_eq!(super::parse_line_auto("two1nine"), 29);
+        assert_eq!(super::parse_line_auto(""), 0);
+        let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..1000 {
+            let n = 1 + rng.below(10);
+            let s = generate(&mut rng, n);
+            assert_eq!(
+                super::parse_line_auto(&s),
+                super::part2::parse_line(&s),
+                "failing case: {:?}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn digit_with_pos_agrees() {
+        use super::part2::*;
+        let mut rng = XorShift(0x853c_49e6_748f_ea9b);
+        for _ in 0..10_000 {
+            let n = 1 + rng.below(10);
+            let s = generate(&mut rng, n);
+            let lhs = match (first_digit_with_pos(&s), last_digit_with_pos(&s)) {
+                (Some((i, d_1)), Some((j, d_0))) => {
+                    assert!(i <= j, "failing case: {:?}", s);
+                    Some(d_1 * 10 + d_0)
+                }
+                (None, None) => None,
+                _ => panic!("failing case: {:?}", s),
+            };
+            assert_eq!(lhs, try_parse_line(&s), "failing case: {:?}", s);
+        }
+    }
+
+    #[test]
+    fn part2_implementations_agree() {
+        let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+        for _ in 0..100_000 {
+            let n = 1 + rng.below(10);
+            let s = generate(&mut rng, n);
+            assert_eq!(
+                super::part2::parse_line(&s),
+                super::part2_alt::parse_line(&s),
+                "failing case: {:?}",
+                s
+            );
+        }
+    }
+}
@@ -2,52 +2,50 @@ use crate::grid::*;
 use std::cell::RefCell;
 use std::convert::TryFrom;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
 use std::ops::Index;
 use std::ops::IndexMut;
 use std::path::Path;
 use std::rc::Rc;
 use std::str::FromStr;
-use std::{fmt, fs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{fmt, fs, thread};
 
-fn start_up(grid: &Grid<Tile>, idx: (usize, usize)) -> Option<Direction> {
-    if idx.0 != 0 {
-        match grid[(idx.0 - 1, idx.1)] {
-            Vert | SW | SE => Some(Up),
-            _ => None,
-        }
-    } else {
-        None
-    }
+/// `Some(dir)` if the neighbor of the start in direction `dir` connects back to it.
+fn start_toward(grid: &Grid<Tile>, idx: (usize, usize), dir: Direction) -> Option<Direction> {
+    let (di, dj) = dir.delta();
+    let i = idx.0.checked_add_signed(di)?;
+    let j = idx.1.checked_add_signed(dj)?;
+    grid.get(i, j)?
+        .ports()
+        .contains(&dir.inverse())
+        .then_some(dir)
 }
-fn start_down(grid: &Grid<Tile>, idx: (usize, usize)) -> Option<Direction> {
-    if idx.0 + 1 != grid.n_rows {
-        match grid[(idx.0 + 1, idx.1)] {
-            Vert | NW | NE => Some(Down),
-            _ => None,
-        }
-    } else {
-        None
-    }
-}
-fn start_left(grid: &Grid<Tile>, idx: (usize, usize)) -> Option<Direction> {
-    if idx.1 != 0 {
-        match grid[(idx.0 - 1, idx.1)] {
-            Horz | NE | SE => Some(Left),
-            _ => None,
-        }
-    } else {
-        None
-    }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MazeError {
+    /// The lines are not all of the same length.
+    UnbalancedRows,
+    NoStart,
+    InvalidTile(char),
+    /// No pipe adjacent to the start connects to it.
+    CannotInitVisitor,
+    /// The main loop does not return to the start.
+    OpenLoop,
+    /// The file could not be read.
+    Io(String),
 }
-fn start_right(grid: &Grid<Tile>, idx: (usize, usize)) -> Option<Direction> {
-    if idx.1 + 1 != grid.n_cols {
-        match grid[(idx.0, idx.1 + 1)] {
-            Horz | NW | SW => Some(Right),
-            _ => None,
+
+impl fmt::Display for MazeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use MazeError::*;
+        match self {
+            UnbalancedRows => write!(f, "rows are not of equal length"),
+            NoStart => write!(f, "maze has no start"),
+            InvalidTile(c) => write!(f, "invalid tile: {}", c),
+            CannotInitVisitor => write!(f, "no pipe connects to the start"),
+            OpenLoop => write!(f, "main loop does not return to the start"),
+            Io(e) => write!(f, "{}", e),
         }
-    } else {
-        None
     }
 }
 
@@ -59,34 +57,76 @@ pub struct Maze {
 }
 
 impl FromStr for Maze {
-    type Err = String;
+    type Err = MazeError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let grid = s.parse::<Grid<Tile>>()?;
+        let mut rows = Vec::new();
+        for line in s.lines() {
+            let mut row = Vec::with_capacity(line.len());
+            for c in line.chars() {
+                row.push(Tile::try_from(c).map_err(|_| MazeError::InvalidTile(c))?);
+            }
+            rows.push(row);
+        }
+        let grid = Grid::from_rows(rows).map_err(|_| MazeError::UnbalancedRows)?;
         let idx = grid
             .inner
             .iter()
             .position(|x| *x == Start)
-            .ok_or_else(|| s.to_string())?;
+            .ok_or(MazeError::NoStart)?;
         let start = Grid::<Tile>::cartesian_index(grid.n_rows, idx);
-        let dir = start_up(&grid, start)
-            .or_else(|| start_down(&grid, start))
-            .or_else(|| start_left(&grid, start))
-            .or_else(|| start_right(&grid, start))
-            .ok_or_else(|| s.to_string())?;
-        Ok(Maze { grid, start, dir })
+        let dir = [North, South, West, East]
+            .into_iter()
+            .find_map(|dir| start_toward(&grid, start, dir))
+            .ok_or(MazeError::CannotInitVisitor)?;
+        let maze = Maze { grid, start, dir };
+        Visitor2::from(&maze)
+            .try_traverse()
+            .map_err(|_| MazeError::OpenLoop)?;
+        Ok(maze)
     }
 }
 
 impl Maze {
+    /// The tile at `(i, j)`, or `None` if out of bounds.
+    pub fn get(&self, i: usize, j: usize) -> Option<Tile> {
+        self.grid.get(i, j).copied()
+    }
+    /// The coordinate of the start.
+    pub fn start(&self) -> (usize, usize) {
+        self.start
+    }
+    pub fn shape(&self) -> (usize, usize) {
+        self.grid.shape()
+    }
     pub fn farthest(&self) -> usize {
+        self.loop_length() / 2
+    }
+    /// The total number of steps required to traverse the main loop.
+    pub fn loop_length(&self) -> usize {
         let mut vis = Visitor2::from(self);
         vis.try_move(self.dir.clone());
         vis.visit();
-        vis.steps / 2
+        vis.loop_length()
+    }
+    /// The coordinates of the main loop, in order of traversal, beginning at the start.
+    pub fn main_loop(&self) -> Vec<(usize, usize)> {
+        Visitor2::from(self).traverse_collecting()
+    }
+    /// The pipe which is hidden beneath the start, as determined by the
+    /// directions in which the main loop leaves and re-enters it.
+    pub fn start_tile(&self) -> Tile {
+        let mut vis = Visitor2::from(self);
+        vis.try_move(self.dir);
+        vis.visit();
+        Tile::from_connections(self.dir, vis.dir.inverse()).unwrap_or(Start)
+    }
+    /// The number of tiles enclosed by the main loop.
+    pub fn enclosed(&self) -> usize {
+        EscapeAnalyzer::from(self).classify_even_odd()
     }
 
-    pub fn from_path<T: AsRef<Path>>(path: T) -> Result<Self, String> {
-        let s = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    pub fn from_path<T: AsRef<Path>>(path: T) -> Result<Self, MazeError> {
+        let s = fs::read_to_string(path).map_err(|e| MazeError::Io(e.to_string()))?;
         s.parse()
     }
 }
@@ -264,18 +304,47 @@ impl Tile {
     pub fn is_start(&self) -> bool {
         *self == Start
     }
+    /// The pipe which connects the two given directions, if any.
+    pub fn from_connections(lhs: Direction, rhs: Direction) -> Option<Self> {
+        match (lhs, rhs) {
+            (North, South) | (South, North) => Some(Vert),
+            (West, East) | (East, West) => Some(Horz),
+            (North, East) | (East, North) => Some(NE),
+            (North, West) | (West, North) => Some(NW),
+            (South, West) | (West, South) => Some(SW),
+            (South, East) | (East, South) => Some(SE),
+            _ => None,
+        }
+    }
+    /// The directions in which the tile connects to its neighbors; the start,
+    /// whose pipe is hidden, connects in none.
+    pub fn ports(&self) -> &'static [Direction] {
+        match self {
+            Vert => &[North, South],
+            Horz => &[West, East],
+            NE => &[North, East],
+            NW => &[North, West],
+            SW => &[South, West],
+            SE => &[South, East],
+            Ground | Start => &[],
+        }
+    }
+    /// Whether the tile has a connection on its north side.
+    pub fn connects_north(&self) -> bool {
+        matches!(*self, Vert | NE | NW)
+    }
     pub fn mainloop_redirect(&self, visitor_dir: Direction) -> Option<Direction> {
         match (self, visitor_dir) {
-            (Vert, x @ (Up | Down)) => Some(x),
-            (Horz, x @ (Left | Right)) => Some(x),
-            (NE, Down) => Some(Right),
-            (NE, Left) => Some(Up),
-            (NW, Down) => Some(Left),
-            (NW, Right) => Some(Up),
-            (SW, Right) => Some(Down),
-            (SW, Up) => Some(Left),
-            (SE, Left) => Some(Down),
-            (SE, Up) => Some(Right),
+            (Vert, x @ (North | South)) => Some(x),
+            (Horz, x @ (West | East)) => Some(x),
+            (NE, South) => Some(East),
+            (NE, West) => Some(North),
+            (NW, South) => Some(West),
+            (NW, East) => Some(North),
+            (SW, East) => Some(South),
+            (SW, North) => Some(West),
+            (SE, West) => Some(South),
+            (SE, North) => Some(East),
             _ => None,
         }
     }
@@ -365,26 +434,8 @@ impl fmt::Display for Tile {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-    // Entry,
-}
+pub use crate::grid::Direction;
 use Direction::*;
-
-impl Direction {
-    pub fn inverse(&self) -> Self {
-        match self {
-            Up => Down,
-            Down => Up,
-            Left => Right,
-            Right => Left,
-        }
-    }
-}
 // #[derive(Debug)]
 // pub struct Visitor<'a> {
 //     idx: (usize, usize),
@@ -544,20 +595,20 @@ impl Visitor2<'_> {
     }
     pub fn try_move(&mut self, dir: Direction) -> bool {
         let state = match dir {
-            Up => {
-                self.dir = Up;
+            North => {
+                self.dir = North;
                 self.move_up()
             }
-            Down => {
-                self.dir = Down;
+            South => {
+                self.dir = South;
                 self.move_down()
             }
-            Left => {
-                self.dir = Left;
+            West => {
+                self.dir = West;
                 self.move_left()
             }
-            Right => {
-                self.dir = Right;
+            East => {
+                self.dir = East;
                 self.move_right()
             }
         };
@@ -574,8 +625,45 @@ impl Visitor2<'_> {
             None => false,
         }
     }
+    /// Visit until the loop closes or a dead end is reached; as a loop cannot
+    /// be longer than the grid, the number of steps is capped at its length.
     pub fn visit(&mut self) {
-        while self.advance() {}
+        let cap = self.grid.len();
+        while self.steps < cap && self.advance() {}
+    }
+    /// Traverse the loop from the start, returning its length if it closes
+    /// (i.e. returns to the start).
+    pub fn try_traverse(&mut self) -> Result<usize, String> {
+        if self.try_move(self.dir) {
+            self.visit();
+        }
+        if self.grid[self.current].is_start() && self.steps != 0 {
+            Ok(self.steps)
+        } else {
+            Err(format!(
+                "loop does not close: stopped at {:?} after {} steps",
+                self.current, self.steps
+            ))
+        }
+    }
+    /// The number of steps taken thus far; after a complete visit, this is
+    /// the length of the loop.
+    pub fn loop_length(&self) -> usize {
+        self.steps
+    }
+    /// Visit the loop from the start, recording each coordinate (including the start)
+    /// in order of traversal.
+    pub fn traverse_collecting(&mut self) -> Vec<(usize, usize)> {
+        let mut path = vec![self.current];
+        if self.try_move(self.dir) {
+            while !self.grid[self.current].is_start() {
+                path.push(self.current);
+                if !self.advance() {
+                    break;
+                }
+            }
+        }
+        path
     }
 }
 
@@ -615,6 +703,11 @@ impl EscapeAnalyzer<'_> {
             self.states.borrow_mut()[vis.current] = MainLoop;
         }
     }
+    /// Flood the tiles reachable from the border without crossing the main loop as `Outside`.
+    /// Seeding from every border tile is sound even if the loop (or the start) lies
+    /// on the border: the loop cannot enclose a tile on the border, and border tiles
+    /// of the loop itself are never flooded. Tiles which escape only by squeezing
+    /// between pipes are not reached, hence, remain `Null`.
     pub fn classify_outside(&self) {
         let (n_rows, n_cols) = self.states.borrow().shape();
         for j in [0, n_cols - 1] {
@@ -638,6 +731,219 @@ impl EscapeAnalyzer<'_> {
     }
 }
 
+impl EscapeAnalyzer<'_> {
+    /*
+    Cast a ray along each row, toggling parity upon each crossing of the main loop.
+    Only tiles which connect north are counted as crossings: `F---J` and `L---7`
+    cross once, whereas `F---7` and `L---J` cross twice (or not at all).
+    As no assumption is made about the perimeter, this is correct
+    even if the loop hugs the border.
+     */
+    /// Classify each tile as `MainLoop`, `Inside` or `Outside` by even-odd
+    /// parity, returning the number of tiles inside.
+    pub fn classify_even_odd(&mut self) -> usize {
+        self.reset();
+        self.main_loop();
+        let start = self.maze.start_tile();
+        self.classify_even_odd_imp(start)
+    }
+    /// As `classify_even_odd`, but marking the main loop from its coordinates
+    /// as recorded by `Visitor2::traverse_collecting`, rather than re-walking it.
+    pub fn classify_even_odd_with(&mut self, path: &[(usize, usize)]) -> usize {
+        self.reset();
+        self.mark_main_loop(path);
+        let start = start_tile_of(path);
+        self.classify_even_odd_imp(start)
+    }
+    fn reset(&self) {
+        self.states
+            .borrow_mut()
+            .inner
+            .iter_mut()
+            .for_each(|x| *x = Null);
+    }
+    fn mark_main_loop(&self, path: &[(usize, usize)]) {
+        let mut states = self.states.borrow_mut();
+        for &idx in path {
+            states[idx] = MainLoop;
+        }
+    }
+    fn classify_even_odd_imp(&self, start: Tile) -> usize {
+        let (n_rows, n_cols) = self.maze.grid.shape();
+        let mut states = self.states.borrow_mut();
+        let mut count: usize = 0;
+        for i in 0..n_rows {
+            let mut inside = false;
+            for j in 0..n_cols {
+                if states[(i, j)] == MainLoop {
+                    let tile = match self.maze.grid[(i, j)] {
+                        Start => start,
+                        x => x,
+                    };
+                    if tile.connects_north() {
+                        inside = !inside;
+                    }
+                } else if inside {
+                    states[(i, j)] = Inside;
+                    count += 1;
+                } else {
+                    states[(i, j)] = Outside;
+                }
+            }
+        }
+        count
+    }
+}
+
+impl EscapeAnalyzer<'_> {
+    /*
+    On a grid of twice the resolution (plus a border of one), tile (i, j) occupies
+    cell (2i + 1, 2j + 1), and the cells between tiles are walls only if a pipe
+    of the main loop passes through them. Hence, the outside can squeeze between
+    pipes, and the border of the fine grid is entirely outside, such that a flood
+    from a single corner reaches all of the outside.
+     */
+    /// Classify each tile as `MainLoop`, `Inside` or `Outside` by flooding the outside,
+    /// breadth-first, with each level of the flood distributed across threads;
+    /// returns the number of tiles inside, which agrees with `classify_even_odd`.
+    pub fn classify_flood_parallel(&mut self) -> usize {
+        let n_threads = thread::available_parallelism().map_or(1, |n| n.get());
+        self.classify_flood_imp(n_threads, FLOOD_MIN_CHUNK)
+    }
+    fn classify_flood_imp(&mut self, n_threads: usize, min_chunk: usize) -> usize {
+        self.reset();
+        self.main_loop();
+        let start = self.maze.start_tile();
+        let (n_rows, n_cols) = self.maze.grid.shape();
+        let mut states = self.states.borrow_mut();
+        let mut wall: Grid<bool> = Grid::new_default(2 * n_rows + 1, 2 * n_cols + 1);
+        for j in 0..n_cols {
+            for i in 0..n_rows {
+                if states[(i, j)] == MainLoop {
+                    let tile = match self.maze.grid[(i, j)] {
+                        Start => start,
+                        x => x,
+                    };
+                    let (i, j) = (2 * i + 1, 2 * j + 1);
+                    wall[(i, j)] = true;
+                    for dir in tile.ports() {
+                        let (di, dj) = dir.delta();
+                        wall[((i as isize + di) as usize, (j as isize + dj) as usize)] = true;
+                    }
+                }
+            }
+        }
+        let outside = flood_parallel(&wall, (0, 0), n_threads, min_chunk);
+        let mut count: usize = 0;
+        for j in 0..n_cols {
+            for i in 0..n_rows {
+                if states[(i, j)] != MainLoop {
+                    if outside[(2 * i + 1, 2 * j + 1)].load(Ordering::Relaxed) {
+                        states[(i, j)] = Outside;
+                    } else {
+                        states[(i, j)] = Inside;
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
+    }
+}
+
+/// Frontiers smaller than this are expanded on the calling thread, as the cost
+/// of spawning exceeds the work.
+const FLOOD_MIN_CHUNK: usize = 1 << 12;
+
+/// The cells reachable from `seed` without crossing a wall.
+fn flood_parallel(
+    wall: &Grid<bool>,
+    seed: (usize, usize),
+    n_threads: usize,
+    min_chunk: usize,
+) -> Grid<AtomicBool> {
+    let (n_rows, n_cols) = wall.shape();
+    let visited: Grid<AtomicBool> = Grid::new_default(n_rows, n_cols);
+    if wall.get(seed.0, seed.1) != Some(&false) {
+        return visited;
+    }
+    visited[seed].store(true, Ordering::Relaxed);
+    let mut frontier = vec![seed];
+    while !frontier.is_empty() {
+        let chunk_size = frontier
+            .len()
+            .div_ceil(n_threads.max(1))
+            .max(min_chunk.max(1));
+        frontier = if frontier.len() <= chunk_size {
+            expand_frontier(wall, &visited, &frontier)
+        } else {
+            thread::scope(|s| {
+                let handles: Vec<_> = frontier
+                    .chunks(chunk_size)
+                    .map(|chunk| s.spawn(|| expand_frontier(wall, &visited, chunk)))
+                    .collect();
+                handles
+                    .into_iter()
+                    .flat_map(|h| h.join().unwrap())
+                    .collect()
+            })
+        };
+    }
+    visited
+}
+
+/// The unvisited, unwalled neighbors of the `frontier`, each of which is claimed
+/// (i.e. marked as visited) by exactly one caller.
+fn expand_frontier(
+    wall: &Grid<bool>,
+    visited: &Grid<AtomicBool>,
+    frontier: &[(usize, usize)],
+) -> Vec<(usize, usize)> {
+    let mut next = Vec::new();
+    for &(i, j) in frontier {
+        for dir in [North, South, West, East] {
+            let (di, dj) = dir.delta();
+            if let (Some(i), Some(j)) = (i.checked_add_signed(di), j.checked_add_signed(dj)) {
+                if wall.get(i, j) == Some(&false) && !visited[(i, j)].swap(true, Ordering::Relaxed)
+                {
+                    next.push((i, j));
+                }
+            }
+        }
+    }
+    next
+}
+
+/// The pipe hidden beneath the start of a loop, given the coordinates of the loop
+/// in order of traversal, beginning at the start.
+fn start_tile_of(path: &[(usize, usize)]) -> Tile {
+    let toward = |(i, j): (usize, usize)| {
+        [North, South, West, East].into_iter().find(|dir| {
+            let (di, dj) = dir.delta();
+            i.checked_add_signed(di) == Some(path[0].0)
+                && j.checked_add_signed(dj) == Some(path[0].1)
+        })
+    };
+    match (path.get(1), path.last()) {
+        (Some(&next), Some(&last)) => match (toward(next), toward(last)) {
+            (Some(lhs), Some(rhs)) => Tile::from_connections(lhs.inverse(), rhs.inverse()),
+            _ => None,
+        },
+        _ => None,
+    }
+    .unwrap_or(Start)
+}
+
+impl<'a> From<&'a Maze> for EscapeAnalyzer<'a> {
+    fn from(maze: &'a Maze) -> Self {
+        let (n_rows, n_cols) = maze.grid.shape();
+        Self {
+            maze,
+            states: Rc::new(RefCell::new(Grid::new_default(n_rows, n_cols))),
+        }
+    }
+}
+
 impl<'a> fmt::Display for EscapeAnalyzer<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let (n_rows, n_cols) = self.maze.grid.shape();
@@ -1317,6 +1623,120 @@ LJ...";
         assert_eq!(maze.farthest(), 8);
     }
 
+    #[test]
+    fn traverse_collecting() {
+        let maze = TEST1.parse::<Maze>().unwrap();
+        assert_eq!(maze.loop_length(), 8);
+        let mut vis = Visitor2::from(&maze);
+        let path = vis.traverse_collecting();
+        assert_eq!(vis.loop_length(), 8);
+        assert_eq!(
+            path,
+            vec![
+                (1, 1),
+                (2, 1),
+                (3, 1),
+                (3, 2),
+                (3, 3),
+                (2, 3),
+                (1, 3),
+                (1, 2)
+            ]
+        );
+        assert_eq!(maze.main_loop(), path);
+
+        let maze = TEST2.parse::<Maze>().unwrap();
+        assert_eq!(maze.main_loop().len(), maze.loop_length());
+    }
+
+    #[test]
+    fn try_traverse() {
+        let maze = TEST2.parse::<Maze>().unwrap();
+        assert_eq!(Visitor2::from(&maze).try_traverse(), Ok(16));
+
+        let s = "\
+.S-7.
+.|.|.
+.L-..";
+        // Construct directly, as parsing rejects a loop which does not close.
+        let grid = Grid::from_rows(
+            s.lines()
+                .map(|line| line.chars().map(|c| Tile::try_from(c).unwrap()).collect())
+                .collect(),
+        )
+        .unwrap();
+        let maze = Maze {
+            grid,
+            start: (0, 1),
+            dir: South,
+        };
+        assert!(Visitor2::from(&maze).try_traverse().is_err());
+        assert_eq!(s.parse::<Maze>(), Err(MazeError::OpenLoop));
+        assert_eq!("S-7\n..|\n...".parse::<Maze>(), Err(MazeError::OpenLoop));
+    }
+
+    #[test]
+    fn ports() {
+        assert_eq!(Vert.ports(), &[North, South]);
+        assert_eq!(Horz.ports(), &[West, East]);
+        assert_eq!(NE.ports(), &[North, East]);
+        assert_eq!(NW.ports(), &[North, West]);
+        assert_eq!(SW.ports(), &[South, West]);
+        assert_eq!(SE.ports(), &[South, East]);
+        assert!(Ground.ports().is_empty());
+        assert!(Start.ports().is_empty());
+        for tile in [Vert, Horz, NE, NW, SW, SE] {
+            let ports = tile.ports();
+            assert_eq!(Tile::from_connections(ports[0], ports[1]), Some(tile));
+            assert_eq!(tile.connects_north(), ports.contains(&North));
+        }
+    }
+
+    #[test]
+    fn accessors() {
+        let maze = TEST1.parse::<Maze>().unwrap();
+        assert_eq!(maze.start(), (1, 1));
+        assert_eq!(maze.get(1, 1), Some(Start));
+        assert_eq!(maze.get(1, 2), Some(Horz));
+        assert_eq!(maze.get(3, 3), Some(NW));
+        assert_eq!(maze.shape(), (5, 5));
+        assert_eq!(maze.get(5, 0), None);
+        assert_eq!(maze.get(0, 5), None);
+    }
+
+    #[test]
+    fn maze_error() {
+        assert_eq!(
+            TEST1.replace('S', "F").parse::<Maze>(),
+            Err(MazeError::NoStart)
+        );
+        assert_eq!(
+            TEST1.replacen('.', "x", 1).parse::<Maze>(),
+            Err(MazeError::InvalidTile('x'))
+        );
+        assert_eq!(
+            ".S-7.\n.|.|\n.L-J.".parse::<Maze>(),
+            Err(MazeError::UnbalancedRows)
+        );
+        // Nothing to the left of the start, which must not be mistaken for above.
+        assert_eq!(".S.".parse::<Maze>(), Err(MazeError::CannotInitVisitor));
+        assert_eq!("".parse::<Maze>(), Err(MazeError::NoStart));
+    }
+
+    #[test]
+    fn from_path() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("aoc_2023_day10_{}.txt", std::process::id()));
+        fs::write(&path, TEST1).unwrap();
+        assert_eq!(Maze::from_path(&path), TEST1.parse::<Maze>());
+        fs::write(&path, TEST1.replacen('.', "x", 1)).unwrap();
+        assert_eq!(Maze::from_path(&path), Err(MazeError::InvalidTile('x')));
+        fs::write(&path, TEST1.replace('S', "F")).unwrap();
+        assert_eq!(Maze::from_path(&path), Err(MazeError::NoStart));
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(Maze::from_path(&path), Err(MazeError::Io(_))));
+    }
+
     // #[test]
     // fn escape_outside() {
     //     let maze = TEST3.parse::<Maze>().unwrap();
@@ -1344,40 +1764,137 @@ LJ...";
 .L--J.L--J.
 ...........";
 
-    //     static TEST4: &str = "\
-    // ..........
-    // .S------7.
-    // .|F----7|.
-    // .||....||.
-    // .||....||.
-    // .|L-7F-J|.
-    // .|..||..|.
-    // .L--JL--J.
-    // ..........";
-
-    //     static TEST5: &str = "\
-    // .F----7F7F7F7F-7....
-    // .|F--7||||||||FJ....
-    // .||.FJ||||||||L7....
-    // FJL7L7LJLJ||LJ.L-7..
-    // L--J.L7...LJS7F-7L7.
-    // ....F-J..F7FJ|L7L7L7
-    // ....L7.F7||L7|.L7L7|
-    // .....|FJLJ|FJ|F7|.LJ
-    // ....FJL-7.||.||||...
-    // ....L---J.LJ.LJLJ...";
-
-    //     static TEST6: &str = "\
-    // FF7FSF7F7F7F7F7F---7
-    // L|LJ||||||||||||F--J
-    // FL-7LJLJ||||||LJL-77
-    // F--JF--7||LJLJ7F7FJ-
-    // L---JF-JLJ.||-FJLJJ7
-    // |F|F-JF---7F7-L7L|7|
-    // |FFJF7L7F-JF7|JL---7
-    // 7-L-JL7||F7|L7F-7F7|
-    // L.L7LFJ|||||FJL7||LJ
-    // L7JLJL-JLJLJL--JLJ.L";
+    static TEST4: &str = "\
+..........
+.S------7.
+.|F----7|.
+.||....||.
+.||....||.
+.|L-7F-J|.
+.|..||..|.
+.L--JL--J.
+..........";
+
+    static TEST5: &str = "\
+.F----7F7F7F7F-7....
+.|F--7||||||||FJ....
+.||.FJ||||||||L7....
+FJL7L7LJLJ||LJ.L-7..
+L--J.L7...LJS7F-7L7.
+....F-J..F7FJ|L7L7L7
+....L7.F7||L7|.L7L7|
+.....|FJLJ|FJ|F7|.LJ
+....FJL-7.||.||||...
+....L---J.LJ.LJLJ...";
+
+    static TEST6: &str = "\
+FF7FSF7F7F7F7F7F---7
+L|LJ||||||||||||F--J
+FL-7LJLJ||||||LJL-77
+F--JF--7||LJLJ7F7FJ-
+L---JF-JLJ.||-FJLJJ7
+|F|F-JF---7F7-L7L|7|
+|FFJF7L7F-JF7|JL---7
+7-L-JL7||F7|L7F-7F7|
+L.L7LFJ|||||FJL7||LJ
+L7JLJL-JLJLJL--JLJ.L";
+
+    #[test]
+    fn classify_even_odd() {
+        for (s, n) in [(TEST3, 4), (TEST4, 4), (TEST5, 8), (TEST6, 10)] {
+            let maze = s.parse::<Maze>().unwrap();
+            let mut esc = EscapeAnalyzer::from(&maze);
+            assert_eq!(esc.classify_even_odd(), n, "\n{}", esc);
+            assert_eq!(maze.enclosed(), n);
+        }
+    }
+
+    #[test]
+    fn classify_even_odd_with() {
+        for (s, n) in [(TEST3, 4), (TEST4, 4), (TEST5, 8), (TEST6, 10), (BORDER, 5)] {
+            let maze = s.parse::<Maze>().unwrap();
+            let path = maze.main_loop();
+            assert_eq!(start_tile_of(&path), maze.start_tile());
+            let mut esc = EscapeAnalyzer::from(&maze);
+            assert_eq!(esc.classify_even_odd_with(&path), n, "\n{}", esc);
+            let lhs = esc.states.borrow().clone();
+            esc.classify_even_odd();
+            assert_eq!(lhs, *esc.states.borrow());
+        }
+        assert_eq!(start_tile_of(&[(0, 0)]), Start);
+    }
+
+    #[test]
+    fn classify_flood_parallel() {
+        for (s, n) in [(TEST3, 4), (TEST4, 4), (TEST5, 8), (TEST6, 10), (BORDER, 5)] {
+            let maze = s.parse::<Maze>().unwrap();
+            let mut esc = EscapeAnalyzer::from(&maze);
+            assert_eq!(esc.classify_even_odd(), n);
+            let serial = esc.states.borrow().clone();
+            assert_eq!(esc.classify_flood_parallel(), n, "\n{}", esc);
+            assert_eq!(*esc.states.borrow(), serial);
+            // Force the frontier to be split across threads, even for small mazes.
+            assert_eq!(esc.classify_flood_imp(4, 1), n, "\n{}", esc);
+            assert_eq!(*esc.states.borrow(), serial);
+        }
+    }
+
+    static BORDER: &str = "\
+S---7
+|...|
+|.F-J
+|.|..
+L-J..";
+
+    #[test]
+    fn classify_even_odd_border() {
+        let maze = BORDER.parse::<Maze>().unwrap();
+        assert_eq!(maze.start_tile(), SE);
+        let mut esc = EscapeAnalyzer::from(&maze);
+        assert_eq!(esc.classify_even_odd(), 5, "\n{}", esc);
+        assert_eq!(esc.states.borrow()[(3, 4)], Outside);
+        assert_eq!(esc.states.borrow()[(1, 3)], Inside);
+    }
+
+    static HUG: &str = "\
+S--7
+|..|
+L--J";
+
+    #[test]
+    fn classify_outside_border() {
+        for (s, n) in [(BORDER, 5), (HUG, 2), (TEST4, 4), (TEST6, 10)] {
+            let maze = s.parse::<Maze>().unwrap();
+            let mut esc = EscapeAnalyzer::from(&maze);
+            assert_eq!(esc.classify_even_odd(), n, "\n{}", esc);
+            let parity = esc.states.borrow().clone();
+
+            let esc = EscapeAnalyzer::from(&maze);
+            esc.main_loop();
+            esc.classify_outside();
+            let states = esc.states.borrow();
+            let (n_rows, n_cols) = states.shape();
+            for i in 0..n_rows {
+                for j in 0..n_cols {
+                    let on_border = i == 0 || j == 0 || i == n_rows - 1 || j == n_cols - 1;
+                    match states[(i, j)] {
+                        // Never marks an enclosed tile as outside.
+                        Outside => assert_eq!(parity[(i, j)], Outside, "{:?}\n{}", (i, j), s),
+                        MainLoop => assert_eq!(parity[(i, j)], MainLoop),
+                        // Unreached tiles are never on the border.
+                        _ => assert!(!on_border, "{:?}\n{}", (i, j), s),
+                    }
+                }
+            }
+        }
+        // The loop occupies the entire border, thus, nothing is flooded.
+        let maze = HUG.parse::<Maze>().unwrap();
+        let esc = EscapeAnalyzer::from(&maze);
+        esc.main_loop();
+        esc.classify_outside();
+        assert!(esc.states.borrow().inner.iter().all(|x| *x != Outside));
+    }
+
     //     #[test]
     //     fn try_bottom() {
     //         let grid = TEST4.parse::<Grid>().unwrap();
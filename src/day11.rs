@@ -3,6 +3,13 @@ use std::ops::{Index, IndexMut};
 use std::path::Path;
 use std::str::FromStr;
 use std::{fmt, fs};
+
+#[cfg(test)]
+thread_local! {
+    /// The number of calls to `Grid::empty_rows`, such that tests may observe reuse.
+    static EMPTY_ROWS_CALLS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Grid {
     // Galaxy: true, EmptySpace: false
@@ -80,6 +87,8 @@ impl Grid {
         !self.inner[idx..idx + n_rows].iter().any(|x| *x)
     }
     pub fn empty_rows(&self) -> Vec<usize> {
+        #[cfg(test)]
+        EMPTY_ROWS_CALLS.with(|n| n.set(n.get() + 1));
         let n_rows = self.n_rows();
         let mut v = Vec::with_capacity(n_rows);
         for i in 0..n_rows {
@@ -182,6 +191,7 @@ impl fmt::Display for Grid {
         Ok(())
     }
 }
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Galaxies {
     inner: Vec<(usize, usize)>,
 }
@@ -202,6 +212,20 @@ impl<'a> From<&'a Grid> for Galaxies {
 }
 
 impl Galaxies {
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+    /// The coordinates of each galaxy, in column-major order of the grid
+    /// from which they originate.
+    pub fn coordinates(&self) -> &[(usize, usize)] {
+        &self.inner
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &(usize, usize)> {
+        self.inner.iter()
+    }
     pub fn manhattan_distances(&self) -> Vec<usize> {
         let n = self.inner.len();
         if n > 1 {
@@ -220,7 +244,21 @@ impl Galaxies {
         }
     }
     pub fn sum_manhattan_distances(&self) -> usize {
-        self.manhattan_distances().into_iter().sum()
+        self.sum_distances_with(|x, y| x.0.abs_diff(y.0) + x.1.abs_diff(y.1))
+    }
+    /// The sum of the distances between each pair of galaxies, under the given metric.
+    pub fn sum_distances_with<F>(&self, metric: F) -> usize
+    where
+        F: Fn((usize, usize), (usize, usize)) -> usize,
+    {
+        let n = self.inner.len();
+        let mut sum: usize = 0;
+        for i in 0..n {
+            for j in i + 1..n {
+                sum += metric(self.inner[i], self.inner[j]);
+            }
+        }
+        sum
     }
 }
 
@@ -237,35 +275,132 @@ impl Galaxies {
 /// Likewise, `galaxies.inner` would benefit from being split into struct of array
 /// (one could re-use a single array for this purpose).
 pub fn expanded_universe(grid: &Grid, factor: NonZeroUsize) -> Galaxies {
-    let factor = factor.get();
-    let f = factor - 1;
-    let empty_rows = grid.empty_rows();
-    let empty_cols = grid.empty_columns();
+    expanded_universe_axes(grid, factor, factor)
+}
+
+/// As `expanded_universe`, but with separate expansion factors for
+/// the empty rows and the empty columns.
+pub fn expanded_universe_axes(
+    grid: &Grid,
+    row_factor: NonZeroUsize,
+    col_factor: NonZeroUsize,
+) -> Galaxies {
+    let axes = EmptyAxes::from(grid);
     let galaxies = Galaxies::from(grid);
+    expand_imp(&axes, &galaxies, row_factor, col_factor)
+}
+
+/// As `expanded_universe`, but re-using the empty axes and galaxies, which
+/// do not depend on the expansion factor.
+pub fn expanded_universe_with(
+    axes: &EmptyAxes,
+    galaxies: &Galaxies,
+    factor: NonZeroUsize,
+) -> Galaxies {
+    expand_imp(axes, galaxies, factor, factor)
+}
+
+fn expand_imp(
+    axes: &EmptyAxes,
+    galaxies: &Galaxies,
+    row_factor: NonZeroUsize,
+    col_factor: NonZeroUsize,
+) -> Galaxies {
+    let f = col_factor.get() - 1;
     let mut inner = galaxies.inner.clone();
-    for j in empty_cols {
+    for j in axes.empty_cols.iter() {
         for (orig, new) in galaxies.inner.iter().zip(inner.iter_mut()) {
             // if orig.1 > j {
             //     new.1 += f;
             // }
             // Or, to make it branchless:
-            let s = (orig.1 > j) as usize;
+            let s = (orig.1 > *j) as usize;
             new.1 += f * s;
         }
     }
-    for i in empty_rows {
+    let f = row_factor.get() - 1;
+    for i in axes.empty_rows.iter() {
         for (orig, new) in galaxies.inner.iter().zip(inner.iter_mut()) {
             // if orig.0 > i {
             //     new.0 += f;
             // }
             // Or, to make it branchless:
-            let s = (orig.0 > i) as usize;
+            let s = (orig.0 > *i) as usize;
             new.0 += f * s;
         }
     }
     Galaxies { inner }
 }
 
+/// The empty rows and columns of a grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmptyAxes {
+    empty_rows: Vec<usize>,
+    empty_cols: Vec<usize>,
+}
+impl<'a> From<&'a Grid> for EmptyAxes {
+    fn from(grid: &'a Grid) -> Self {
+        Self {
+            empty_rows: grid.empty_rows(),
+            empty_cols: grid.empty_columns(),
+        }
+    }
+}
+impl EmptyAxes {
+    pub fn empty_rows(&self) -> &[usize] {
+        &self.empty_rows
+    }
+    pub fn empty_cols(&self) -> &[usize] {
+        &self.empty_cols
+    }
+}
+
+/// A parsed grid together with the quantities which do not depend on the
+/// expansion factor, i.e. the empty axes and the galaxy coordinates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Universe {
+    grid: Grid,
+    axes: EmptyAxes,
+    galaxies: Galaxies,
+}
+impl From<Grid> for Universe {
+    fn from(grid: Grid) -> Self {
+        let axes = EmptyAxes::from(&grid);
+        let galaxies = Galaxies::from(&grid);
+        Self {
+            grid,
+            axes,
+            galaxies,
+        }
+    }
+}
+impl FromStr for Universe {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<Grid>().map(Self::from)
+    }
+}
+impl Universe {
+    pub fn from_path<T: AsRef<Path>>(path: T) -> Result<Self, String> {
+        Grid::from_path(path).map(Self::from)
+    }
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+    pub fn axes(&self) -> &EmptyAxes {
+        &self.axes
+    }
+    pub fn galaxies(&self) -> &Galaxies {
+        &self.galaxies
+    }
+    pub fn expand(&self, factor: NonZeroUsize) -> Galaxies {
+        expanded_universe_with(&self.axes, &self.galaxies, factor)
+    }
+    pub fn sum_distances(&self, factor: NonZeroUsize) -> usize {
+        self.expand(factor).sum_manhattan_distances()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -334,6 +469,21 @@ mod tests {
         assert_eq!(lhs, EXPAND);
     }
 
+    #[test]
+    fn galaxies_accessors() {
+        let grid = TEST.parse::<Grid>().unwrap();
+        let galaxies = Galaxies::from(&grid);
+        assert_eq!(galaxies.len(), TEST.matches('#').count());
+        assert_eq!(galaxies.len(), 9);
+        assert!(!galaxies.is_empty());
+        assert_eq!(galaxies.iter().count(), 9);
+        assert_eq!(galaxies.coordinates()[0], (2, 0));
+        assert!(galaxies.iter().all(|&(i, j)| grid[(i, j)]));
+
+        let grid = "...\n...".parse::<Grid>().unwrap();
+        assert!(Galaxies::from(&grid).is_empty());
+    }
+
     #[test]
     fn sum_manhattan_distances() {
         let mut grid = TEST.parse::<Grid>().unwrap();
@@ -352,4 +502,90 @@ mod tests {
         let galaxies = expanded_universe(&grid, NonZeroUsize::new(100).unwrap());
         assert_eq!(galaxies.sum_manhattan_distances(), 8410);
     }
+    #[test]
+    fn expanded_universe_with_works() {
+        let calls = || EMPTY_ROWS_CALLS.with(|n| n.get());
+        let grid = TEST.parse::<Grid>().unwrap();
+        let before = calls();
+        let axes = EmptyAxes::from(&grid);
+        assert_eq!(axes.empty_rows(), &[3, 7]);
+        assert_eq!(axes.empty_cols(), &[2, 5, 8]);
+        let galaxies = Galaxies::from(&grid);
+        let lhs: Vec<_> = [2, 10, 100]
+            .into_iter()
+            .map(|factor| {
+                expanded_universe_with(&axes, &galaxies, NonZeroUsize::new(factor).unwrap())
+                    .sum_manhattan_distances()
+            })
+            .collect();
+        assert_eq!(lhs, vec![374, 1030, 8410]);
+        // Computed once, by `EmptyAxes::from`, for the whole sweep.
+        assert_eq!(calls() - before, 1);
+    }
+
+    #[test]
+    fn sum_distances_with() {
+        let s = "\
+#..
+...
+.##";
+        let grid = s.parse::<Grid>().unwrap();
+        let galaxies = Galaxies::from(&grid);
+        // (0, 0), (2, 1), (2, 2)
+        let chebyshev =
+            |x: (usize, usize), y: (usize, usize)| x.0.abs_diff(y.0).max(x.1.abs_diff(y.1));
+        assert_eq!(galaxies.sum_distances_with(chebyshev), 2 + 2 + 1);
+        assert_eq!(galaxies.sum_manhattan_distances(), 3 + 4 + 1);
+
+        let grid = TEST.parse::<Grid>().unwrap();
+        let galaxies = expanded_universe(&grid, NonZeroUsize::new(2).unwrap());
+        assert_eq!(
+            galaxies.sum_distances_with(|x, y| x.0.abs_diff(y.0) + x.1.abs_diff(y.1)),
+            374
+        );
+    }
+
+    #[test]
+    fn universe() {
+        let universe = TEST.parse::<Universe>().unwrap();
+        assert_eq!(universe.galaxies().len(), 9);
+        assert_eq!(universe.axes().empty_rows(), &[3, 7]);
+        let f = |factor| universe.sum_distances(NonZeroUsize::new(factor).unwrap());
+        assert_eq!(f(2), 374);
+        assert_eq!(f(10), 1030);
+        assert_eq!(f(1_000_000), 82000210);
+    }
+
+    #[test]
+    fn expanded_universe_axes_works() {
+        let one = NonZeroUsize::new(1).unwrap();
+        let ten = NonZeroUsize::new(10).unwrap();
+        let grid = TEST.parse::<Grid>().unwrap();
+        let lo = expanded_universe(&grid, one).sum_manhattan_distances();
+        let hi = expanded_universe(&grid, ten).sum_manhattan_distances();
+        let lhs = expanded_universe_axes(&grid, one, ten).sum_manhattan_distances();
+        assert!(lo < lhs && lhs < hi);
+        assert_eq!(
+            expanded_universe_axes(&grid, ten, ten).sum_manhattan_distances(),
+            hi
+        );
+
+        let s = "\
+#..
+...
+..#";
+        let grid = s.parse::<Grid>().unwrap();
+        let f = |row_factor, col_factor| {
+            expanded_universe_axes(
+                &grid,
+                NonZeroUsize::new(row_factor).unwrap(),
+                NonZeroUsize::new(col_factor).unwrap(),
+            )
+            .sum_manhattan_distances()
+        };
+        assert_eq!(f(1, 1), 4);
+        assert_eq!(f(1, 10), 2 + 11);
+        assert_eq!(f(10, 1), 11 + 2);
+        assert_eq!(f(2, 3), 3 + 4);
+    }
 }
@@ -1,12 +1,13 @@
 use crate::combinations::*;
+use std::collections::HashMap;
 use std::convert::TryFrom;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
 use std::num::NonZeroUsize;
 use std::path::Path;
 use std::str::FromStr;
+use std::{fmt, thread};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Condition {
     Damaged,
     Operational,
@@ -24,6 +25,20 @@ impl TryFrom<char> for Condition {
         }
     }
 }
+impl From<Condition> for char {
+    fn from(cond: Condition) -> char {
+        match cond {
+            Damaged => '#',
+            Operational => '.',
+            Unknown => '?',
+        }
+    }
+}
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", char::from(*self))
+    }
+}
 impl Condition {
     pub fn is_damaged(&self) -> bool {
         *self == Damaged
@@ -36,7 +51,7 @@ impl Condition {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Row {
     left: Vec<Condition>,
     right: Vec<usize>,
@@ -61,6 +76,23 @@ impl FromStr for Row {
     }
 }
 
+impl fmt::Display for Row {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for cond in self.left.iter() {
+            write!(f, "{}", cond)?;
+        }
+        write!(f, " ")?;
+        let mut iter = self.right.iter();
+        if let Some(size) = iter.next() {
+            write!(f, "{}", size)?;
+            for size in iter {
+                write!(f, ",{}", size)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl Row {
     pub fn is_feasible(&self) -> bool {
         let mut contig_iter = self.right.iter();
@@ -111,6 +143,57 @@ impl Row {
         }
         Self { left, right }
     }
+    /// Trim leading and trailing operational conditions, which have no
+    /// bearing on the number of arrangements; equivalent rows thus
+    /// compare (and hash) equal.
+    pub fn canonicalize(&self) -> Self {
+        let start = self.left.iter().take_while(|x| x.is_operational()).count();
+        let end = self.left.len()
+            - self.left[start..]
+                .iter()
+                .rev()
+                .take_while(|x| x.is_operational())
+                .count();
+        Self {
+            left: self.left[start..end].to_vec(),
+            right: self.right.clone(),
+        }
+    }
+    /// Count the arrangements by dynamic programming over the suffixes of
+    /// the conditions and the contiguous groups: Θ(n * m * k) where n is the number of
+    /// conditions, m the number of groups and k the largest group.
+    pub fn count_arrangements_dp(&self) -> u64 {
+        let n = self.left.len();
+        let m = self.right.len();
+        let stride = m + 1;
+        // ways[i * stride + k] : arrangements of `left[i..]` into `right[k..]`
+        let mut ways = vec![0u64; (n + 1) * stride];
+        ways[n * stride + m] = 1;
+        for i in (0..n).rev() {
+            for k in 0..=m {
+                let mut total: u64 = 0;
+                if !self.left[i].is_damaged() {
+                    total += ways[(i + 1) * stride + k];
+                }
+                if !self.left[i].is_operational() && k < m {
+                    let end = i + self.right[k];
+                    if end <= n
+                        && !self.left[i..end].iter().any(|x| x.is_operational())
+                        && (end == n || !self.left[end].is_damaged())
+                    {
+                        total += ways[(end + 1).min(n) * stride + k + 1];
+                    }
+                }
+                ways[i * stride + k] = total;
+            }
+        }
+        ways[0]
+    }
+    /// Whether any arrangement of the conditions is consistent with the groups;
+    /// a row which is not is malformed.
+    pub fn is_satisfiable(&self) -> bool {
+        self.count_arrangements_dp() != 0
+    }
     pub fn count_condition(&self, cond: Condition) -> usize {
         match cond {
             Damaged => self.left.iter().filter(|cond| cond.is_damaged()).count(),
@@ -207,7 +290,9 @@ impl From<Row> for RowAnalyzer {
             .filter(|(_, cond)| cond.is_unknown())
             .map(|(i, _)| i)
             .collect();
-        let k_damaged = n_damaged - row.count_condition(Damaged);
+        // More damaged than the groups admit is unsatisfiable, which
+        // `is_feasible` rejects for every combination.
+        let k_damaged = n_damaged.saturating_sub(row.count_condition(Damaged));
         // let n_unknown = unknowns.len();
         Self {
             row,
@@ -220,10 +305,10 @@ impl From<Row> for RowAnalyzer {
 }
 
 impl RowAnalyzer {
-    pub fn count_arrangements(&mut self) -> usize {
+    pub fn count_arrangements(&mut self) -> u64 {
         let n_unknown = self.unknowns.len();
         let mut comb = Combinations::new(n_unknown, self.k_damaged);
-        let mut sum: usize = 0;
+        let mut sum: u64 = 0;
         while !comb.is_done() {
             // Set base state
             for i in self.unknowns.iter() {
@@ -234,18 +319,14 @@ impl RowAnalyzer {
                 let idx = self.unknowns[*i];
                 self.row.left[idx] = Damaged;
             }
-            sum += self.row.is_feasible() as usize;
+            sum += self.row.is_feasible() as u64;
             comb.next_combination_mut();
         }
         // Reset to original state
         for i in self.unknowns.iter() {
             self.row.left[*i] = Unknown;
         }
-        if sum == 0 {
-            1
-        } else {
-            sum
-        }
+        sum
     }
 
     // Too high
@@ -280,7 +361,7 @@ impl RowAnalyzer {
     // }
 
     // More nuanced attempt
-    pub fn count_arrangements_with_unfold(&mut self) -> usize {
+    pub fn count_arrangements_with_unfold(&mut self) -> u64 {
         let mut row = self.row.clone();
         let m = row.right.len();
         row.left.push(Unknown);
@@ -354,15 +435,57 @@ impl FromStr for RowAnalyzer {
     }
 }
 
+/// Apply the counter `f` to each row, computing the count for each
+/// distinct row exactly once.
+pub fn count_arrangements_cached<F>(rows: &[Row], mut f: F) -> Vec<u64>
+where
+    F: FnMut(&Row) -> u64,
+{
+    let mut cache: HashMap<&Row, u64> = HashMap::new();
+    rows.iter()
+        .map(|row| *cache.entry(row).or_insert_with(|| f(row)))
+        .collect()
+}
+
+/// The number of arrangements of a single row.
+pub fn count_arrangements(row: &Row) -> u64 {
+    row.count_arrangements_dp()
+}
+
+/// The number of arrangements of a single row, unfolded `factor` times.
+pub fn count_arrangements_unfolded(row: &Row, factor: NonZeroUsize) -> u64 {
+    row.unfold(factor).count_arrangements_dp()
+}
+
+/// Sum the arrangements of each row, unfolded `unfold` times.
+pub fn sum_arrangements(rows: &[Row], unfold: NonZeroUsize) -> u64 {
+    rows.iter()
+        .map(|row| count_arrangements_unfolded(row, unfold))
+        .sum()
+}
+
+/// As `sum_arrangements`, but distributing the rows across threads.
+pub fn sum_arrangements_parallel(rows: &[Row], unfold: NonZeroUsize) -> u64 {
+    let n_threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = rows.len().div_ceil(n_threads).max(1);
+    thread::scope(|s| {
+        let handles: Vec<_> = rows
+            .chunks(chunk_size)
+            .map(|chunk| s.spawn(move || sum_arrangements(chunk, unfold)))
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).sum()
+    })
+}
+
 pub fn rows_from_path<T: AsRef<Path>>(path: T) -> Result<Vec<Row>, String> {
-    let f = File::open(path).map_err(|e| e.to_string())?;
-    let mut f = BufReader::new(f);
-    let mut s = String::with_capacity(256);
+    let s = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    rows_from_str(&s)
+}
+
+pub fn rows_from_str(s: &str) -> Result<Vec<Row>, String> {
     let mut v = Vec::new();
-    while f.read_line(&mut s).map_err(|e| e.to_string())? != 0 {
-        s.pop();
-        v.push(s.parse::<Row>()?);
-        s.clear();
+    for line in s.lines() {
+        v.push(line.parse::<Row>()?);
     }
     Ok(v)
 }
@@ -370,6 +493,7 @@ pub fn rows_from_path<T: AsRef<Path>>(path: T) -> Result<Vec<Row>, String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::XorShift;
 
     #[test]
     fn from_str() {
@@ -465,6 +589,213 @@ mod tests {
         let mut x = s.parse::<RowAnalyzer>().unwrap();
         assert_eq!(x.count_arrangements(), 3);
     }
+    #[test]
+    fn canonicalize() {
+        let lhs = ".#. 1".parse::<Row>().unwrap().canonicalize();
+        let rhs = "#. 1".parse::<Row>().unwrap().canonicalize();
+        assert_eq!(lhs, rhs);
+        assert_eq!(lhs, "# 1".parse::<Row>().unwrap());
+
+        let lhs = "... 1".parse::<Row>().unwrap().canonicalize();
+        assert_eq!(lhs.left, vec![]);
+
+        for (s, n) in [
+            ("???.### 1,1,3", 1),
+            (".??..??...?##. 1,1,3", 4),
+            ("?#?#?#?#?#?#?#? 1,3,1,6", 1),
+            ("????.#...#... 4,1,1", 1),
+            ("????.######..#####. 1,6,5", 4),
+            ("?###???????? 3,2,1", 10),
+        ] {
+            let row = s.parse::<Row>().unwrap();
+            let mut x = RowAnalyzer::from(row.canonicalize());
+            assert_eq!(x.count_arrangements(), n);
+        }
+    }
+    #[test]
+    fn count_arrangements_cached() {
+        let row = ".??..??...?##. 1,1,3".parse::<Row>().unwrap();
+        let rows = vec![row.clone(), row.clone(), row];
+        let mut calls: usize = 0;
+        let counts = super::count_arrangements_cached(&rows, |row| {
+            calls += 1;
+            RowAnalyzer::from(row.clone()).count_arrangements()
+        });
+        assert_eq!(calls, 1);
+        assert_eq!(counts, vec![4, 4, 4]);
+    }
+    static TEST: &str = "\
+???.### 1,1,3
+.??..??...?##. 1,1,3
+?#?#?#?#?#?#?#? 1,3,1,6
+????.#...#... 4,1,1
+????.######..#####. 1,6,5
+?###???????? 3,2,1";
+
+    #[test]
+    fn rows_from_str() {
+        let rows = super::rows_from_str(TEST).unwrap();
+        assert_eq!(rows.len(), 6);
+        let counts: Vec<_> = rows
+            .into_iter()
+            .map(|row| RowAnalyzer::from(row).count_arrangements_with_unfold())
+            .collect();
+        assert_eq!(counts, vec![1, 16384, 1, 16, 2500, 506250]);
+        assert_eq!(counts.into_iter().sum::<u64>(), 525152);
+    }
+
+    #[test]
+    fn row_to_string() {
+        let row = "???.### 1,1,3".parse::<Row>().unwrap();
+        assert_eq!(row.to_string(), "???.### 1,1,3");
+        for line in TEST.lines() {
+            let row = line.parse::<Row>().unwrap();
+            assert_eq!(row.to_string(), line);
+        }
+    }
+
+    #[test]
+    fn count_arrangements_dp() {
+        let rows = super::rows_from_str(TEST).unwrap();
+        let counts: Vec<_> = rows.iter().map(|row| row.count_arrangements_dp()).collect();
+        assert_eq!(counts, vec![1, 4, 1, 1, 4, 10]);
+        let five = NonZeroUsize::new(5).unwrap();
+        let counts: Vec<_> = rows
+            .iter()
+            .map(|row| row.unfold(five).count_arrangements_dp())
+            .collect();
+        assert_eq!(counts, vec![1, 16384, 1, 16, 2500, 506250]);
+    }
+
+    #[test]
+    fn count_arrangements_free() {
+        let row = "?###???????? 3,2,1".parse::<Row>().unwrap();
+        assert_eq!(super::count_arrangements(&row), 10);
+        let one = NonZeroUsize::new(1).unwrap();
+        assert_eq!(count_arrangements_unfolded(&row, one), 10);
+        let five = NonZeroUsize::new(5).unwrap();
+        assert_eq!(count_arrangements_unfolded(&row, five), 506250);
+    }
+
+    #[test]
+    fn counts_exceed_u32() {
+        let row = "?###???????? 3,2,1".parse::<Row>().unwrap();
+        let five = NonZeroUsize::new(5).unwrap();
+        assert_eq!(count_arrangements_unfolded(&row, five), 506250);
+        let rows = vec![row; 10_000];
+        let total: u64 = 506250 * 10_000;
+        assert!(total > u32::MAX as u64);
+        assert_eq!(sum_arrangements(&rows, five), total);
+        assert_eq!(super::sum_arrangements_parallel(&rows, five), total);
+        let counts =
+            super::count_arrangements_cached(&rows, |row| count_arrangements_unfolded(row, five));
+        assert_eq!(counts.into_iter().sum::<u64>(), total);
+    }
+
+    #[test]
+    fn unsatisfiable() {
+        let row = "#. 2".parse::<Row>().unwrap();
+        assert_eq!(row.count_arrangements_dp(), 0);
+        assert!(!row.is_satisfiable());
+        let mut x = RowAnalyzer::from(row);
+        assert_eq!(x.count_arrangements(), 0);
+
+        let row = "##.# 1,1".parse::<Row>().unwrap();
+        assert_eq!(row.count_arrangements_dp(), 0);
+        assert_eq!(RowAnalyzer::from(row).count_arrangements(), 0);
+
+        for row in super::rows_from_str(TEST).unwrap() {
+            assert!(row.is_satisfiable());
+        }
+    }
+
+    /// The contiguous groups of a row in which no condition is unknown.
+    fn groups(left: &[Condition]) -> Vec<usize> {
+        left.split(|cond| !cond.is_damaged())
+            .map(|run| run.len())
+            .filter(|&len| len != 0)
+            .collect()
+    }
+
+    /// Generate a row of at most `max_len` conditions which admits at least one
+    /// arrangement, by masking some of the conditions of a known arrangement;
+    /// occasionally, a group is perturbed, which may render the row unsatisfiable.
+    fn generate(rng: &mut XorShift, max_len: usize) -> Row {
+        let n = 1 + rng.below(max_len);
+        let mut left: Vec<_> = (0..n)
+            .map(|_| {
+                if rng.below(2) == 0 {
+                    Damaged
+                } else {
+                    Operational
+                }
+            })
+            .collect();
+        left[rng.below(n)] = Damaged;
+        let mut right = groups(&left);
+        if rng.below(4) == 0 {
+            let k = rng.below(right.len());
+            right[k] = 1 + rng.below(right[k] + 1);
+        }
+        for cond in left.iter_mut() {
+            if rng.below(3) == 0 {
+                *cond = Unknown;
+            }
+        }
+        Row { left, right }
+    }
+
+    /// Mask additional conditions of `row` as unknown, preserving satisfiability.
+    fn mask(rng: &mut XorShift, row: &Row) -> Row {
+        let mut row = row.clone();
+        for cond in row.left.iter_mut() {
+            if rng.below(8) == 0 {
+                *cond = Unknown;
+            }
+        }
+        row
+    }
+
+    fn brute_force(row: &Row) -> u64 {
+        RowAnalyzer::from(row.clone()).count_arrangements()
+    }
+
+    #[test]
+    fn dp_agrees_with_brute_force() {
+        let rows = super::rows_from_str(TEST).unwrap();
+        for row in rows.iter() {
+            assert_eq!(brute_force(row), row.count_arrangements_dp(), "{}", row);
+        }
+        let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+        for _ in 0..100 {
+            for row in rows.iter() {
+                let row = mask(&mut rng, row);
+                assert_eq!(brute_force(&row), row.count_arrangements_dp(), "{}", row);
+            }
+        }
+        let mut n_unsatisfiable: usize = 0;
+        for _ in 0..2000 {
+            let row = generate(&mut rng, 14);
+            let lhs = brute_force(&row);
+            assert_eq!(lhs, row.count_arrangements_dp(), "{}", row);
+            n_unsatisfiable += (lhs == 0) as usize;
+        }
+        // Both paths, satisfiable and not, are exercised.
+        assert!(0 < n_unsatisfiable && n_unsatisfiable < 2000);
+    }
+
+    #[test]
+    fn sum_arrangements_parallel() {
+        let rows = super::rows_from_str(TEST).unwrap();
+        let five = NonZeroUsize::new(5).unwrap();
+        let serial = sum_arrangements(&rows, five);
+        assert_eq!(serial, 525152);
+        assert_eq!(super::sum_arrangements_parallel(&rows, five), serial);
+        let one = NonZeroUsize::new(1).unwrap();
+        assert_eq!(super::sum_arrangements_parallel(&rows, one), 21);
+        assert_eq!(super::sum_arrangements_parallel(&[], five), 0);
+    }
+
     #[test]
     fn count_arrangements_with_unfold() {
         let s = "???.### 1,1,3";
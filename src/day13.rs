@@ -1,5 +1,7 @@
+use std::collections::hash_map::DefaultHasher;
 use std::fmt;
 use std::fs;
+use std::hash::{Hash, Hasher};
 use std::ops::{Index, IndexMut};
 use std::path::Path;
 use std::str::FromStr;
@@ -17,6 +19,13 @@ impl Reflection {
             Horizontal(n) => Horizontal(n + 1),
         }
     }
+    /// The contribution to the summary: `n` for vertical, `100 * n` for horizontal.
+    pub fn score(&self) -> usize {
+        match self {
+            Vertical(n) => *n,
+            Horizontal(n) => 100 * n,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -73,6 +82,37 @@ impl Grid {
         }
         true
     }
+    /// A hash of each column; equal columns have equal hashes, and a column
+    /// hashes as would the identical row.
+    pub fn column_hashes(&self) -> Vec<u64> {
+        let n_rows = self.n_rows();
+        if n_rows == 0 {
+            return vec![0; self.n_cols()];
+        }
+        self.inner
+            .chunks_exact(n_rows)
+            .map(|column| {
+                let mut state = DefaultHasher::new();
+                for x in column {
+                    x.hash(&mut state);
+                }
+                state.finish()
+            })
+            .collect()
+    }
+    /// A hash of each row; equal rows have equal hashes.
+    pub fn row_hashes(&self) -> Vec<u64> {
+        let n_cols = self.n_cols();
+        (0..self.n_rows())
+            .map(|i| {
+                let mut state = DefaultHasher::new();
+                for j in 0..n_cols {
+                    self[(i, j)].hash(&mut state);
+                }
+                state.finish()
+            })
+            .collect()
+    }
 
     /*
     These are O(n^2) themselves, with O(n) `are_columns_equal`, `are_rows_equal`
@@ -99,7 +139,7 @@ impl Grid {
     }
 
     pub fn find_reflection_vertical(&self) -> Option<usize> {
-        self.find_vertical_bounded(0, self.n_cols - 1)
+        self.find_vertical_bounded(0, self.n_cols.saturating_sub(1))
     }
     fn find_horizontal_bounded(&self, start: usize, end: usize) -> Option<usize> {
         let n_rows = self.n_rows();
@@ -120,7 +160,31 @@ impl Grid {
         None
     }
     pub fn find_reflection_horizontal(&self) -> Option<usize> {
-        self.find_horizontal_bounded(0, self.n_rows - 1)
+        self.find_horizontal_bounded(0, self.n_rows.saturating_sub(1))
+    }
+    /// Every line of vertical reflection, each given by the column to its left.
+    pub fn all_reflections_vertical(&self) -> Vec<usize> {
+        let n_cols = self.n_cols();
+        (0..n_cols.saturating_sub(1))
+            .filter(|&j| {
+                (0..=j)
+                    .rev()
+                    .zip(j + 1..n_cols)
+                    .all(|(left, right)| self.are_columns_equal(left, right))
+            })
+            .collect()
+    }
+    /// Every line of horizontal reflection, each given by the row above it.
+    pub fn all_reflections_horizontal(&self) -> Vec<usize> {
+        let n_rows = self.n_rows();
+        (0..n_rows.saturating_sub(1))
+            .filter(|&i| {
+                (0..=i)
+                    .rev()
+                    .zip(i + 1..n_rows)
+                    .all(|(above, below)| self.are_rows_equal(above, below))
+            })
+            .collect()
     }
     fn find_reflection_imp(&self) -> Option<Reflection> {
         if let Some(n) = self.find_reflection_vertical() {
@@ -137,13 +201,13 @@ impl Grid {
     }
 
     pub fn find_reflection_vertical_avoid(&self, avoid: usize) -> Option<usize> {
-        let actual_end = self.n_cols - 1;
+        let actual_end = self.n_cols.saturating_sub(1);
         let end = avoid.min(actual_end);
         self.find_vertical_bounded(0, end)
             .or_else(|| self.find_vertical_bounded(avoid + 1, actual_end))
     }
     pub fn find_reflection_horizontal_avoid(&self, avoid: usize) -> Option<usize> {
-        let actual_end = self.n_rows - 1;
+        let actual_end = self.n_rows.saturating_sub(1);
         let end = avoid.min(actual_end);
         self.find_horizontal_bounded(0, end)
             .or_else(|| self.find_horizontal_bounded(avoid + 1, actual_end))
@@ -161,20 +225,33 @@ impl Grid {
         }
     }
 
-    fn find_smudged_reflection_imp(&mut self) -> Reflection {
-        let x = self.find_reflection_imp().unwrap();
+    fn find_smudge_imp(&mut self) -> Option<(usize, Reflection)> {
+        let x = self.find_reflection_imp()?;
         let n = self.inner.len();
         let mut i: usize = 0;
         while i < n {
             self.inner[i] ^= true;
             if let Some(y) = self.branch(&x) {
                 self.inner[i] ^= true;
-                return y;
+                return Some((i, y));
             }
             self.inner[i] ^= true;
             i += 1;
         }
-        x
+        None
+    }
+    fn find_smudged_reflection_imp(&mut self) -> Reflection {
+        match self.find_smudge_imp() {
+            Some((_, y)) => y,
+            None => self.find_reflection_imp().unwrap(),
+        }
+    }
+    /// Find the smudge, returning its coordinate along with the reflection
+    /// which results from fixing it. The grid is left unchanged.
+    pub fn find_smudge(&mut self) -> Option<((usize, usize), Reflection)> {
+        let n_rows = self.n_rows();
+        self.find_smudge_imp()
+            .map(|(idx, y)| ((idx % n_rows, idx / n_rows), y.inc()))
     }
     pub fn find_smudged_reflection(&mut self) -> Reflection {
         self.find_smudged_reflection_imp().inc()
@@ -213,6 +290,13 @@ impl FromStr for Grid {
                 inner.push(e);
             }
         }
+        if n_rows == 0 {
+            return Ok(Grid {
+                inner,
+                n_rows: 0,
+                n_cols: 0,
+            });
+        }
         let n_cols = inner.len() / n_rows;
         if inner.len() % n_rows != 0 {
             Err(s.to_string())
@@ -263,13 +347,7 @@ pub fn sum_reflections<F>(f: F, grids: &mut [Grid]) -> usize
 where
     F: FnMut(&mut Grid) -> Option<Reflection>,
 {
-    grids
-        .into_iter()
-        .filter_map(f)
-        .fold(0usize, |acc, x| match x {
-            Vertical(n) => acc + n,
-            Horizontal(n) => acc + 100 * n,
-        })
+    grids.iter_mut().filter_map(f).map(|x| x.score()).sum()
 }
 pub fn sum_reflections_part1(grids: &mut [Grid]) -> usize {
     sum_reflections(|x| x.find_reflection(), grids)
@@ -277,6 +355,14 @@ pub fn sum_reflections_part1(grids: &mut [Grid]) -> usize {
 pub fn sum_reflections_part2(grids: &mut [Grid]) -> usize {
     sum_reflections(|x| Some(x.find_smudged_reflection()), grids)
 }
+/// As `sum_reflections_part2`, but each grid is cloned before the smudge
+/// search, hence, the caller's grids are never mutated, even transiently.
+pub fn sum_reflections_part2_borrowed(grids: &[Grid]) -> usize {
+    grids
+        .iter()
+        .map(|x| x.clone().find_smudged_reflection().score())
+        .sum()
+}
 
 #[cfg(test)]
 mod tests {
@@ -337,6 +423,27 @@ mod tests {
         assert!(x.are_columns_equal(7, 8));
     }
 
+    #[test]
+    fn hashes() {
+        let x = VERT2.parse::<Grid>().unwrap();
+        let h = x.column_hashes();
+        assert_eq!(h.len(), 9);
+        assert_eq!(h[7], h[8]);
+        assert_ne!(h[0], h[1]);
+        let mut y = x.clone();
+        y[(0, 8)] ^= true;
+        let h = y.column_hashes();
+        assert_ne!(h[7], h[8]);
+
+        let x = HORZ.parse::<Grid>().unwrap();
+        let h = x.row_hashes();
+        assert_eq!(h.len(), 7);
+        assert_eq!(h[3], h[4]);
+        assert_eq!(h[2], h[5]);
+        assert_ne!(h[0], h[6]);
+        assert_eq!(x.transpose().column_hashes(), h);
+    }
+
     #[test]
     fn find_reflection_vertical() {
         let x = VERT.parse::<Grid>().unwrap();
@@ -355,6 +462,56 @@ mod tests {
         assert_eq!(idx, 3);
     }
 
+    #[test]
+    fn all_reflections() {
+        let x = VERT.parse::<Grid>().unwrap();
+        assert_eq!(x.all_reflections_vertical(), vec![4]);
+        assert_eq!(x.all_reflections_horizontal(), vec![]);
+        let x = HORZ.parse::<Grid>().unwrap();
+        assert_eq!(x.all_reflections_vertical(), vec![]);
+        assert_eq!(x.all_reflections_horizontal(), vec![3]);
+
+        let x = "#..##..#\n#..##..#".parse::<Grid>().unwrap();
+        let lines = x.all_reflections_vertical();
+        assert_eq!(lines, vec![1, 3, 5]);
+        assert_eq!(x.find_reflection_vertical(), Some(lines[0]));
+        // The avoidance used by `branch` yields the next line of reflection.
+        assert_eq!(x.find_reflection_vertical_avoid(1), Some(3));
+        assert_eq!(x.all_reflections_horizontal(), vec![0]);
+        assert_eq!(x.transpose().all_reflections_horizontal(), lines);
+    }
+
+    #[test]
+    fn degenerate() {
+        // A single row admits only vertical reflections.
+        let x = "#..##..#".parse::<Grid>().unwrap();
+        assert_eq!(x.find_reflection_horizontal(), None);
+        assert_eq!(x.find_reflection_horizontal_avoid(0), None);
+        assert_eq!(x.find_reflection(), Some(Vertical(2)));
+        // A single column admits only horizontal reflections.
+        let x = "#\n.\n.\n#".parse::<Grid>().unwrap();
+        assert_eq!(x.find_reflection_vertical(), None);
+        assert_eq!(x.find_reflection_vertical_avoid(0), None);
+        assert_eq!(x.find_reflection(), Some(Horizontal(2)));
+        // A single square admits neither.
+        let x = "#".parse::<Grid>().unwrap();
+        assert_eq!(x.find_reflection(), None);
+        // Nor does an empty grid, which must not underflow.
+        let x = "".parse::<Grid>().unwrap();
+        assert_eq!((x.n_rows(), x.n_cols()), (0, 0));
+        assert_eq!(x.find_reflection(), None);
+        assert_eq!(x.find_reflection_vertical_avoid(0), None);
+        assert_eq!(x.find_reflection_horizontal_avoid(3), None);
+        assert!(x.all_reflections_vertical().is_empty());
+    }
+
+    #[test]
+    fn score() {
+        assert_eq!(Vertical(5).score(), 5);
+        assert_eq!(Horizontal(4).score(), 400);
+        assert_eq!(Horizontal(0).score(), 0);
+    }
+
     #[test]
     fn sum_reflections_works() {
         let mut grids = grids_from_str(TEST).unwrap();
@@ -362,6 +519,33 @@ mod tests {
         assert_eq!(sum_reflections_part2(&mut grids), 400);
     }
 
+    #[test]
+    fn sum_reflections_part2_borrowed_works() {
+        let grids = grids_from_str(TEST).unwrap();
+        let orig = grids.clone();
+        assert_eq!(sum_reflections_part2_borrowed(&grids), 400);
+        assert_eq!(grids, orig);
+    }
+
+    #[test]
+    fn find_smudge() {
+        let mut x = VERT.parse::<Grid>().unwrap();
+        let orig = x.clone();
+        let (pos, lhs) = x.find_smudge().unwrap();
+        assert_eq!(x, orig);
+        assert_eq!(lhs, Horizontal(3));
+        assert_eq!(x.find_reflection(), Some(Vertical(5)));
+        x[pos] ^= true;
+        assert_eq!(x.find_reflection_horizontal(), Some(2));
+
+        let mut x = HORZ.parse::<Grid>().unwrap();
+        let (pos, lhs) = x.find_smudge().unwrap();
+        assert_eq!(lhs, Horizontal(1));
+        assert_eq!(x.find_reflection_horizontal(), Some(3));
+        x[pos] ^= true;
+        assert_eq!(x.find_reflection_horizontal(), Some(0));
+    }
+
     #[test]
     fn fix_smudge() {
         let mut x = VERT.parse::<Grid>().unwrap();
@@ -40,18 +40,58 @@ impl fmt::Display for Square {
     }
 }
 
+pub use crate::grid::Direction;
+use Direction::*;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Platform(Grid<Square>);
 
 impl Platform {
+    /// Construct from row-major rows, which must be of equal length.
+    pub fn from_rows(rows: Vec<Vec<Square>>) -> Result<Self, String> {
+        Grid::from_rows(rows).map(Platform)
+    }
+    pub fn grid(&self) -> &Grid<Square> {
+        &self.0
+    }
+    pub fn shape(&self) -> (usize, usize) {
+        self.0.shape()
+    }
+    /// The square at `(i, j)`, or `None` if out of bounds.
+    pub fn get(&self, i: usize, j: usize) -> Option<Square> {
+        self.0.get(i, j).copied()
+    }
+    /// The number of cubes, ground and spheres, respectively; tilting conserves each.
+    pub fn counts(&self) -> (usize, usize, usize) {
+        self.0
+            .inner
+            .iter()
+            .fold((0, 0, 0), |(cube, ground, sphere), x| match x {
+                Cube => (cube + 1, ground, sphere),
+                Ground => (cube, ground + 1, sphere),
+                Sphere => (cube, ground, sphere + 1),
+            })
+    }
     pub fn total_load(&self) -> usize {
+        self.load_toward(North)
+    }
+    /// The load on the edge in direction `dir`, i.e. each sphere contributes
+    /// its distance (in rows or columns, inclusive) from the opposite edge.
+    pub fn load_toward(&self, dir: Direction) -> usize {
         let n_rows = self.0.n_rows();
         let n_cols = self.0.n_cols();
         let mut sum: usize = 0;
         for j in 0..n_cols {
             for i in 0..n_rows {
                 match self.0[(i, j)] {
-                    Sphere => sum += n_rows - i,
+                    Sphere => {
+                        sum += match dir {
+                            North => n_rows - i,
+                            South => i + 1,
+                            West => n_cols - j,
+                            East => j + 1,
+                        }
+                    }
                     _ => (),
                 }
             }
@@ -171,19 +211,45 @@ impl Platform {
         self.tilt_east();
     }
 
+    fn fingerprint(&self) -> u64 {
+        let mut state = DefaultHasher::new();
+        self.0.inner.hash(&mut state);
+        state.finish()
+    }
+
+    /// The total load after each of (at most) the first `n` spin cycles, stopping
+    /// at the first cycle which yields a previously seen state; the final load
+    /// then repeats an earlier one, exposing the periodic pattern.
+    pub fn cycle_loads(&mut self, n: usize) -> Vec<usize> {
+        let mut seen = HashSet::new();
+        let mut loads = Vec::new();
+        for _ in 0..n {
+            self.spin_cycle();
+            loads.push(self.total_load());
+            if !seen.insert(self.fingerprint()) {
+                break;
+            }
+        }
+        loads
+    }
+
     pub fn cycle_and_compute_load(&mut self, n: usize) -> usize {
         if n != 0 {
             let mut cache = HashSet::new();
+            let mut prev = Vec::new();
             let mut i: usize = 0;
             // Find the first cycle (if it exists)
             while i < n {
-                let mut state = DefaultHasher::new();
-                self.0.inner.hash(&mut state);
-                if !cache.insert(state.finish()) {
+                if !cache.insert(self.fingerprint()) {
                     break;
                 }
+                prev.clone_from(&self.0.inner);
                 self.spin_cycle();
                 i += 1;
+                // A fixed point: all subsequent spin cycles are no-ops.
+                if self.0.inner == prev {
+                    return self.total_load();
+                }
             }
             // Then, iterate until the cycle length is unchanging.
             let mut m: usize = 0;
@@ -191,18 +257,19 @@ impl Platform {
                 m = cache.len();
                 cache.clear();
                 while i < n {
-                    let mut state = DefaultHasher::new();
-                    self.0.inner.hash(&mut state);
-                    if !cache.insert(state.finish()) {
+                    if !cache.insert(self.fingerprint()) {
                         break;
                     }
                     self.spin_cycle();
                     i += 1;
                 }
             }
-            let rem = (n - i) % m;
-            for _ in 0..rem {
-                self.spin_cycle();
+            // If `n` was reached before a cycle was found, there is nothing left to do.
+            if i < n {
+                let rem = (n - i) % m;
+                for _ in 0..rem {
+                    self.spin_cycle();
+                }
             }
         }
         self.total_load()
@@ -248,6 +315,33 @@ O.#..O.#.#
         assert_eq!(x.to_string(), NORTH);
     }
 
+    #[test]
+    fn accessors() {
+        let mut x = TEST.parse::<Platform>().unwrap();
+        assert_eq!(x.shape(), (10, 10));
+        assert_eq!(x.get(0, 2), Some(Ground));
+        x.tilt_north();
+        assert_eq!(x.get(0, 0), Some(Sphere));
+        assert_eq!(x.get(0, 2), Some(Sphere));
+        assert_eq!(x.get(0, 5), Some(Cube));
+        assert_eq!(x.get(10, 0), None);
+        assert_eq!(x.get(0, 10), None);
+        assert_eq!(x.grid().shape(), x.shape());
+    }
+
+    #[test]
+    fn counts() {
+        let mut x = TEST.parse::<Platform>().unwrap();
+        let counts = x.counts();
+        assert_eq!(counts, (17, 65, 18));
+        x.tilt_north();
+        assert_eq!(x.counts(), counts);
+        x.tilt_south();
+        assert_eq!(x.counts(), counts);
+        x.spin_cycle();
+        assert_eq!(x.counts(), counts);
+    }
+
     static NORTH: &str = "\
 OOOO.#.O..
 OO..#....#
@@ -260,12 +354,74 @@ O..#.OO...
 #....###..
 #....#....";
 
+    #[test]
+    fn tilt_rectangular() {
+        let mut x = "O.#O\n.O..\nO..O".parse::<Platform>().unwrap();
+        assert_eq!(x.to_string(), "O.#O\n.O..\nO..O");
+        x.tilt_north();
+        assert_eq!(x.to_string(), "OO#O\nO..O\n....");
+        assert_eq!(x.total_load(), 3 + 3 + 3 + 2 + 2);
+        x.tilt_east();
+        assert_eq!(x.to_string(), "OO#O\n..OO\n....");
+    }
+
+    #[test]
+    fn ragged() {
+        let x = "O.#O\n.O..\nO..O".parse::<Platform>().unwrap();
+        assert_eq!(x.0.shape(), (3, 4));
+        // Were the lengths merely summed, this would parse as 3 x 4.
+        let e = "O.#O\n.O...\nO..".parse::<Platform>().unwrap_err();
+        assert_eq!(e, "line 1 has length 5, expected 4");
+        let e = "O.#O\n.O..\nO..".parse::<Platform>().unwrap_err();
+        assert_eq!(e, "line 2 has length 3, expected 4");
+    }
+
     #[test]
     fn total_load() {
         let x = NORTH.parse::<Platform>().unwrap();
         assert_eq!(x.total_load(), 136);
     }
 
+    #[test]
+    fn from_rows() {
+        let x = Platform::from_rows(vec![
+            vec![Sphere, Ground, Cube],
+            vec![Ground, Sphere, Ground],
+            vec![Sphere, Ground, Sphere],
+        ])
+        .unwrap();
+        assert_eq!(x, "O.#\n.O.\nO.O".parse::<Platform>().unwrap());
+        // Untilted: 3 for row 0, 2 for row 1, 1 for each of the two in row 2.
+        assert_eq!(x.total_load(), 3 + 2 + 1 + 1);
+        assert_eq!(x.load_toward(West), 3 + 2 + 3 + 1);
+        assert!(Platform::from_rows(vec![vec![Sphere], vec![]]).is_err());
+    }
+
+    #[test]
+    fn load_toward() {
+        let x = NORTH.parse::<Platform>().unwrap();
+        assert_eq!(x.load_toward(North), x.total_load());
+
+        // Tilting the vertically mirrored platform south must yield
+        // the southern load equal to the northern load of the original.
+        let flipped = TEST.lines().rev().collect::<Vec<_>>().join("\n");
+        let mut x = flipped.parse::<Platform>().unwrap();
+        x.tilt_south();
+        assert_eq!(x.load_toward(South), 136);
+
+        // Likewise for the horizontally mirrored platform.
+        let mirrored = TEST
+            .lines()
+            .map(|line| line.chars().rev().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut x = TEST.parse::<Platform>().unwrap();
+        x.tilt_west();
+        let mut y = mirrored.parse::<Platform>().unwrap();
+        y.tilt_east();
+        assert_eq!(x.load_toward(West), y.load_toward(East));
+    }
+
     static CYCLE1: &str = "\
 .....#....
 ....#...O#
@@ -317,4 +473,40 @@ O..#.OO...
         let lhs = x.cycle_and_compute_load(1_000_000_000);
         assert_eq!(lhs, 64);
     }
+
+    #[test]
+    fn cycle_and_compute_load_fixed_point() {
+        let mut x = "###\n###".parse::<Platform>().unwrap();
+        assert_eq!(x.cycle_and_compute_load(1_000_000_000), 0);
+        // The sphere is boxed in, hence, cannot move.
+        let mut x = "O#\n##".parse::<Platform>().unwrap();
+        assert_eq!(x.cycle_and_compute_load(usize::MAX), 2);
+        assert_eq!(x.to_string(), "O#\n##");
+        // Reaches the fixed point after a single spin cycle.
+        let mut x = "..\n.O".parse::<Platform>().unwrap();
+        assert_eq!(x.cycle_and_compute_load(usize::MAX), 1);
+        assert_eq!(x.to_string(), "..\n.O");
+        let mut x = "O.\n..".parse::<Platform>().unwrap();
+        assert_eq!(x.cycle_and_compute_load(1), 1);
+        assert_eq!(x.to_string(), "..\n.O");
+        // Fewer spin cycles than are needed to find the cycle.
+        let mut x = TEST.parse::<Platform>().unwrap();
+        assert_eq!(x.cycle_and_compute_load(3), 69);
+        assert_eq!(x.to_string(), CYCLE3);
+    }
+
+    #[test]
+    fn cycle_loads() {
+        let mut x = TEST.parse::<Platform>().unwrap();
+        let loads = x.cycle_loads(1_000_000_000);
+        assert_eq!(loads, vec![87, 69, 69, 69, 65, 64, 65, 63, 68, 69]);
+        // The state after cycle 10 is that after cycle 3.
+        let (mu, period) = (2, 7);
+        assert_eq!(loads.len() - 1 - mu, period);
+        assert_eq!(loads[mu + (999_999_999 - mu) % period], 64);
+
+        let mut x = TEST.parse::<Platform>().unwrap();
+        assert_eq!(x.cycle_loads(3), vec![87, 69, 69]);
+        assert_eq!(x.to_string(), CYCLE3);
+    }
 }
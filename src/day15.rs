@@ -4,9 +4,13 @@
 use std::convert::TryFrom;
 use std::{fs, io, path::Path};
 
+/// The HASH algorithm, which is defined over ASCII, hence, raw bytes.
+pub fn hash_bytes(b: &[u8]) -> u8 {
+    b.iter()
+        .fold(0u8, |h, &c| h.wrapping_add(c).wrapping_mul(17))
+}
 pub fn hash(s: &str) -> u8 {
-    s.chars()
-        .fold(0u8, |h, c| h.wrapping_add(c as u8).wrapping_mul(17))
+    hash_bytes(s.as_bytes())
 }
 
 pub fn init_seq_sum(s: &str) -> u32 {
@@ -62,18 +66,21 @@ impl<'a> HashMap<'a> {
 }
 
 impl HashMap<'_> {
+    fn box_powers(&self) -> impl Iterator<Item = usize> + '_ {
+        self.boxes.iter().zip(1usize..).map(|(bx, i)| {
+            i * bx
+                .iter()
+                .zip(1usize..)
+                .map(|(lens, j)| lens.focal as usize * j)
+                .sum::<usize>()
+        })
+    }
     pub fn focusing_power(&self) -> usize {
-        self.boxes
-            .iter()
-            .zip(1usize..)
-            .map(|(bx, i)| {
-                i * bx
-                    .iter()
-                    .zip(1usize..)
-                    .map(|(lens, j)| lens.focal as usize * j)
-                    .sum::<usize>()
-            })
-            .sum()
+        self.box_powers().sum()
+    }
+    /// The contribution of each box to the focusing power, indexed by box.
+    pub fn focusing_power_by_box(&self) -> Vec<usize> {
+        self.box_powers().collect()
     }
 }
 
@@ -141,6 +148,14 @@ mod tests {
         assert_eq!(lhs, rhs);
     }
 
+    #[test]
+    fn hash_bytes_works() {
+        assert_eq!(hash_bytes(b"HASH"), 52);
+        for x in TEST.split(',') {
+            assert_eq!(hash(x), hash_bytes(x.as_bytes()));
+        }
+    }
+
     #[test]
     fn init_seq_sum_works() {
         assert_eq!(init_seq_sum(TEST), 1320);
@@ -198,4 +213,17 @@ mod tests {
         let map = HashMap::try_from(TEST).unwrap();
         assert_eq!(map.focusing_power(), 145);
     }
+    #[test]
+    fn focusing_power_by_box() {
+        let map = HashMap::try_from(TEST).unwrap();
+        let v = map.focusing_power_by_box();
+        assert_eq!(v.len(), 256);
+        assert_eq!(v.iter().sum::<usize>(), 145);
+        assert_eq!(v[0], 5);
+        assert_eq!(v[3], 140);
+        assert!(v
+            .iter()
+            .enumerate()
+            .all(|(i, &x)| x == 0 || i == 0 || i == 3));
+    }
 }
@@ -1,27 +1,12 @@
 use crate::grid::*;
 use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::rc::Rc;
 use std::str::FromStr;
 use std::{fmt, fs};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
-impl Direction {
-    pub fn inverse(&self) -> Self {
-        match self {
-            Up => Down,
-            Down => Up,
-            Left => Right,
-            Right => Left,
-        }
-    }
-}
+pub use crate::grid::Direction;
 use Direction::*;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -37,19 +22,19 @@ use Elem::*;
 impl Elem {
     pub fn redirect(&self, tracer_dir: Direction) -> (Direction, Option<Direction>) {
         match (self, tracer_dir) {
-            (Empty, x) | (SplitVert, x @ (Up | Down)) | (SplitHorz, x @ (Left | Right)) => {
+            (Empty, x) | (SplitVert, x @ (North | South)) | (SplitHorz, x @ (West | East)) => {
                 (x, None)
             }
-            (SplitVert, Left | Right) => (Up, Some(Down)),
-            (SplitHorz, Up | Down) => (Left, Some(Right)),
-            (MirrorUp, Right) => (Up, None),
-            (MirrorUp, Left) => (Down, None),
-            (MirrorUp, Down) => (Left, None),
-            (MirrorUp, Up) => (Right, None),
-            (MirrorDown, Right) => (Down, None),
-            (MirrorDown, Left) => (Up, None),
-            (MirrorDown, Up) => (Left, None),
-            (MirrorDown, Down) => (Right, None),
+            (SplitVert, West | East) => (North, Some(South)),
+            (SplitHorz, North | South) => (West, Some(East)),
+            (MirrorUp, East) => (North, None),
+            (MirrorUp, West) => (South, None),
+            (MirrorUp, South) => (West, None),
+            (MirrorUp, North) => (East, None),
+            (MirrorDown, East) => (South, None),
+            (MirrorDown, West) => (North, None),
+            (MirrorDown, North) => (West, None),
+            (MirrorDown, South) => (East, None),
         }
     }
 }
@@ -84,43 +69,141 @@ impl fmt::Display for Elem {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Contraption(Grid<Elem>);
+#[cfg(test)]
+thread_local! {
+    /// The number of beams traced on a cache miss, such that tests may observe hits.
+    static TRACES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Energization counts keyed by the starting position and direction of the beam.
+type Cache = RefCell<HashMap<((usize, usize), Direction), usize>>;
+
+/// The layout, along with a cache of energization counts.
+#[derive(Debug, Clone)]
+pub struct Contraption(Grid<Elem>, Cache);
+
+// The cache is an implementation detail, hence, does not participate in equality.
+impl PartialEq for Contraption {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+impl Eq for Contraption {}
 
 impl Contraption {
+    /// Discard all cached energization counts.
+    pub fn invalidate_cache(&mut self) {
+        self.1.get_mut().clear();
+    }
+    /// Replace the element at `(i, j)`, discarding the cache as the layout has changed.
+    /// Panics if out of bounds.
+    pub fn set(&mut self, i: usize, j: usize, elem: Elem) {
+        self.0[(i, j)] = elem;
+        self.invalidate_cache();
+    }
     pub fn ray_trace(&self) -> Grid<Mark> {
-        if self.0.len() == 0 {
-            Grid::new_default(0, 0)
-        } else {
-            let (n_rows, n_cols) = self.0.shape();
-            let states = Rc::new(RefCell::new(Grid::new_default(n_rows, n_cols)));
-            {
+        self.ray_trace_with()
+    }
+    /// As `ray_trace`, but recording the traversal using the given `Marker`.
+    pub fn ray_trace_with<M: Marker>(&self) -> Grid<M> {
+        self.ray_trace_seeds(&[((0, 0), East)])
+    }
+    /// Trace a beam from each of the `seeds`, recording all on a single grid, such that
+    /// a position traversed by several beams is marked once; out of bounds seeds are ignored.
+    fn ray_trace_seeds<M: Marker>(&self, seeds: &[((usize, usize), Direction)]) -> Grid<M> {
+        let (n_rows, n_cols) = self.0.shape();
+        let states = Rc::new(RefCell::new(Grid::new_default(n_rows, n_cols)));
+        for &(current, dir) in seeds {
+            if self.0.get(current.0, current.1).is_some() {
                 let mut tracer = Tracer {
-                    current: (0, 0),
-                    dir: Right,
+                    current,
+                    dir,
                     layout: &self.0,
                     states: Rc::clone(&states),
                 };
                 tracer.trace();
             }
-            Rc::into_inner(states).unwrap().into_inner()
         }
+        Rc::into_inner(states).unwrap().into_inner()
     }
     pub fn from_path<T: AsRef<Path>>(path: T) -> Result<Self, String> {
         let s = fs::read_to_string(path).map_err(|e| e.to_string())?;
         s.parse::<Self>()
     }
     pub fn count_energized(&self) -> usize {
+        self.count_energized_with::<Mark>()
+    }
+    pub fn count_energized_with<M: Marker>(&self) -> usize {
         if self.0.len() == 0 {
             0
         } else {
-            self.ray_trace()
+            self.ray_trace_with::<M>()
                 .inner
                 .into_iter()
                 .fold(0usize, |acc, x| acc + x.any() as usize)
         }
     }
-    fn ray_trace_imp(&self, i: usize, j: usize, dir: Direction, states: Rc<RefCell<Grid<Mark>>>) {
+    /// The number of cells energized by beams entering simultaneously at each of the `seeds`.
+    pub fn count_energized_multi(&self, seeds: &[((usize, usize), Direction)]) -> usize {
+        self.ray_trace_seeds::<Mark>(seeds)
+            .inner
+            .into_iter()
+            .fold(0usize, |acc, x| acc + x.any() as usize)
+    }
+    /// The number of energized cells traversed horizontally and vertically, respectively;
+    /// a cell traversed in both orientations contributes to both.
+    pub fn beam_stats(&self) -> (usize, usize) {
+        self.ray_trace()
+            .inner
+            .iter()
+            .fold((0usize, 0usize), |(horz, vert), x| {
+                (
+                    horz + (x.is_left() | x.is_right()) as usize,
+                    vert + (x.is_up() | x.is_down()) as usize,
+                )
+            })
+    }
+    /// Check the trace from the origin against the (position, direction) states
+    /// reachable from the origin, re-derived by a search independent of `Tracer`:
+    /// every mark must be reachable, and every reachable state must be marked.
+    pub fn verify_trace(&self) -> bool {
+        self.0.len() == 0 || self.verify_marks(&self.ray_trace())
+    }
+    fn verify_marks(&self, marks: &Grid<Mark>) -> bool {
+        // The outgoing directions from each position, as marked by `Tracer::try_move`.
+        let mut reached: HashSet<((usize, usize), Direction)> = HashSet::new();
+        let mut seen: HashSet<((usize, usize), Direction)> = HashSet::new();
+        let mut queue = vec![((0, 0), East)];
+        seen.insert(((0, 0), East));
+        while let Some(((i, j), incoming)) = queue.pop() {
+            let (first, second) = self.0[(i, j)].redirect(incoming);
+            for dir in std::iter::once(first).chain(second) {
+                reached.insert(((i, j), dir));
+                let (di, dj) = dir.delta();
+                if let (Some(i), Some(j)) = (i.checked_add_signed(di), j.checked_add_signed(dj)) {
+                    if self.0.get(i, j).is_some() && seen.insert(((i, j), dir)) {
+                        queue.push(((i, j), dir));
+                    }
+                }
+            }
+        }
+        let (n_rows, n_cols) = self.0.shape();
+        marks.shape() == (n_rows, n_cols)
+            && (0..n_cols).all(|j| {
+                (0..n_rows).all(|i| {
+                    [North, South, East, West]
+                        .into_iter()
+                        .all(|dir| marks[(i, j)].is_marked(dir) == reached.contains(&((i, j), dir)))
+                })
+            })
+    }
+    fn ray_trace_imp<M: Marker>(
+        &self,
+        i: usize,
+        j: usize,
+        dir: Direction,
+        states: Rc<RefCell<Grid<M>>>,
+    ) {
         states.borrow_mut().inner.iter_mut().for_each(|x| x.reset());
         let mut tracer = Tracer {
             current: (i, j),
@@ -130,38 +213,62 @@ impl Contraption {
         };
         tracer.trace();
     }
+    fn count_energized_imp<M: Marker>(
+        &self,
+        i: usize,
+        j: usize,
+        dir: Direction,
+        states: &Rc<RefCell<Grid<M>>>,
+    ) -> usize {
+        if let Some(total) = self.1.borrow().get(&((i, j), dir)) {
+            return *total;
+        }
+        #[cfg(test)]
+        TRACES.with(|n| n.set(n.get() + 1));
+        self.ray_trace_imp(i, j, dir, Rc::clone(states));
+        let total = states
+            .borrow()
+            .inner
+            .iter()
+            .fold(0usize, |acc, x| acc + x.any() as usize);
+        self.1.borrow_mut().insert(((i, j), dir), total);
+        total
+    }
     pub fn maximum_energized(&self) -> usize {
+        self.maximum_energized_with::<Mark>()
+    }
+    pub fn maximum_energized_with<M: Marker>(&self) -> usize {
+        self.energization_map_with::<M>()
+            .into_iter()
+            .fold(0usize, |mx, (_, _, total)| mx.max(total))
+    }
+    /// The number of energized cells for each beam entering from the edge,
+    /// keyed by the starting position and direction; corners appear once per
+    /// direction of entry, hence there are `2 * (n_rows + n_cols)` entries.
+    pub fn energization_map(&self) -> Vec<((usize, usize), Direction, usize)> {
+        self.energization_map_with::<Mark>()
+    }
+    pub fn energization_map_with<M: Marker>(&self) -> Vec<((usize, usize), Direction, usize)> {
         if self.0.len() == 0 {
-            0
+            Vec::new()
         } else {
             let (n_rows, n_cols) = self.0.shape();
-            let states = Rc::new(RefCell::new(Grid::new_default(n_rows, n_cols)));
-            let mut mx: usize = 0;
+            let states: Rc<RefCell<Grid<M>>> =
+                Rc::new(RefCell::new(Grid::new_default(n_rows, n_cols)));
+            let mut v = Vec::with_capacity(2 * (n_rows + n_cols));
             let right = n_cols - 1;
             let bottom = n_rows - 1;
-            for (dir, j) in [(Right, 0), (Left, right)] {
+            for (dir, j) in [(East, 0), (West, right)] {
                 for i in 0..n_rows {
-                    self.ray_trace_imp(i, j, dir, Rc::clone(&states));
-                    let total = states
-                        .borrow()
-                        .inner
-                        .iter()
-                        .fold(0usize, |acc, x| acc + x.any() as usize);
-                    mx = mx.max(total);
+                    v.push(((i, j), dir, self.count_energized_imp(i, j, dir, &states)));
                 }
             }
-            for (dir, i) in [(Down, 0), (Up, bottom)] {
+            for (dir, i) in [(South, 0), (North, bottom)] {
                 for j in 0..n_cols {
-                    self.ray_trace_imp(i, j, dir, Rc::clone(&states));
-                    let total = states
-                        .borrow()
-                        .inner
-                        .iter()
-                        .fold(0usize, |acc, x| acc + x.any() as usize);
-                    mx = mx.max(total);
+                    v.push(((i, j), dir, self.count_energized_imp(i, j, dir, &states)));
                 }
             }
-            mx
+            v
         }
     }
 }
@@ -169,7 +276,21 @@ impl Contraption {
 impl FromStr for Contraption {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Contraption(s.parse::<Grid<Elem>>()?))
+        // Locate the first invalid character, as the grid reports only the character.
+        for (i, line) in s.lines().enumerate() {
+            for (j, c) in line.chars().enumerate() {
+                if Elem::try_from(c).is_err() {
+                    return Err(format!(
+                        "invalid character {:?} at row {}, column {}",
+                        c, i, j
+                    ));
+                }
+            }
+        }
+        Ok(Contraption(
+            s.parse::<Grid<Elem>>()?,
+            RefCell::new(HashMap::new()),
+        ))
     }
 }
 
@@ -179,9 +300,33 @@ impl fmt::Display for Contraption {
     }
 }
 
+/// The directions in which a position has been traversed.
+pub trait Marker: Default + Clone {
+    fn mark(&mut self, dir: Direction);
+    fn reset(&mut self);
+    fn any(&self) -> bool;
+    fn is_marked(&self, dir: Direction) -> bool;
+    #[inline]
+    fn is_up(&self) -> bool {
+        self.is_marked(North)
+    }
+    #[inline]
+    fn is_down(&self) -> bool {
+        self.is_marked(South)
+    }
+    #[inline]
+    fn is_left(&self) -> bool {
+        self.is_marked(West)
+    }
+    #[inline]
+    fn is_right(&self) -> bool {
+        self.is_marked(East)
+    }
+}
+
 /*
-Yes, we could implement this 16-state wonder on a `u8`, but
-for ease of use we'll allow 3 more bytes.
+Yes, we could implement this 16-state wonder on a `u8` (see `PackedMark`),
+but for ease of use we'll allow 3 more bytes.
 */
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Mark {
@@ -190,70 +335,75 @@ pub struct Mark {
     left: bool,
     right: bool,
 }
-impl Mark {
-    pub fn mark(&mut self, dir: Direction) {
+impl Marker for Mark {
+    fn mark(&mut self, dir: Direction) {
         match dir {
-            Up => self.up = true,
-            Down => self.down = true,
-            Left => self.left = true,
-            Right => self.right = true,
+            North => self.up = true,
+            South => self.down = true,
+            West => self.left = true,
+            East => self.right = true,
         }
     }
     #[inline]
-    pub fn reset(&mut self) {
+    fn reset(&mut self) {
         self.up = false;
         self.down = false;
         self.left = false;
         self.right = false;
     }
     #[inline]
-    pub fn any(&self) -> bool {
+    fn any(&self) -> bool {
         self.up | self.down | self.left | self.right
     }
+    #[inline]
+    fn is_marked(&self, dir: Direction) -> bool {
+        match dir {
+            North => self.up,
+            South => self.down,
+            West => self.left,
+            East => self.right,
+        }
+    }
 }
 
-/*
-The `u8` impl would be:
-
 const UP: u8 = 0x08;
 const DOWN: u8 = 0x04;
 const LEFT: u8 = 0x02;
 const RIGHT: u8 = 0x01;
+
+/// A `Mark` packed into the low 4 bits of a single byte.
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
-pub struct Mark(u8);
-impl Mark {
-pub fn is_up(&self) -> bool {
-self.0 & UP == UP
-    }
-    pub fn is_down(&self) -> bool {
-        self.0 & DOWN == DOWN
-    }
-    pub fn is_left(&self) -> bool {
-        self.0 & LEFT == LEFT
-    }
-    pub fn is_right(&self) -> bool {
-        self.0 & RIGHT == RIGHT
-    }
-    pub fn set_up(&mut self) {
-        self.0 |= UP
-    }
-    pub fn set_down(&mut self) {
-        self.0 |= DOWN
+pub struct PackedMark(u8);
+impl PackedMark {
+    #[inline]
+    fn bit(dir: Direction) -> u8 {
+        match dir {
+            North => UP,
+            South => DOWN,
+            West => LEFT,
+            East => RIGHT,
+        }
     }
-    pub fn set_left(&mut self) {
-        self.0 |= LEFT
+}
+impl Marker for PackedMark {
+    #[inline]
+    fn mark(&mut self, dir: Direction) {
+        self.0 |= Self::bit(dir)
     }
-    pub fn set_right(&mut self) {
-        self.0 |= RIGHT
+    #[inline]
+    fn reset(&mut self) {
+        self.0 = 0;
     }
-    pub fn any(&self) -> bool {
+    #[inline]
+    fn any(&self) -> bool {
         self.0 != 0
     }
-    pub fn reset(&mut self) {
-        self.0 = 0;
+    #[inline]
+    fn is_marked(&self, dir: Direction) -> bool {
+        let bit = Self::bit(dir);
+        self.0 & bit == bit
     }
 }
-*/
 
 /*
 
@@ -322,13 +472,13 @@ whether this is worthwhile is determined by the sparsity of the graph --
 increasing sparsity makes this more likely to be a beneficial tradeoff.
 */
 #[derive(Debug, Clone)]
-pub struct Tracer<'a> {
+pub struct Tracer<'a, M = Mark> {
     current: (usize, usize),
     dir: Direction,
     layout: &'a Grid<Elem>,
-    states: Rc<RefCell<Grid<Mark>>>,
+    states: Rc<RefCell<Grid<M>>>,
 }
-impl Tracer<'_> {
+impl<'a, M: Marker> Tracer<'a, M> {
     pub fn move_up(&mut self) -> bool {
         if self.current.0 != 0 {
             self.current.0 -= 1;
@@ -364,53 +514,27 @@ impl Tracer<'_> {
         }
     }
     pub fn try_move(&mut self, dir: Direction) -> bool {
-        match dir {
-            Up => {
-                self.dir = Up;
-                if self.states.borrow()[self.current].up {
-                    false
-                } else {
-                    self.states.borrow_mut()[self.current].up = true;
-                    self.move_up()
-                }
-            }
-            Down => {
-                self.dir = Down;
-                if self.states.borrow()[self.current].down {
-                    false
-                } else {
-                    self.states.borrow_mut()[self.current].down = true;
-                    self.move_down()
-                }
-            }
-            Left => {
-                self.dir = Left;
-                if self.states.borrow()[self.current].left {
-                    false
-                } else {
-                    self.states.borrow_mut()[self.current].left = true;
-                    self.move_left()
-                }
-            }
-            Right => {
-                self.dir = Right;
-                if self.states.borrow()[self.current].right {
-                    false
-                } else {
-                    self.states.borrow_mut()[self.current].right = true;
-                    self.move_right()
-                }
+        self.dir = dir;
+        if self.states.borrow()[self.current].is_marked(dir) {
+            false
+        } else {
+            self.states.borrow_mut()[self.current].mark(dir);
+            match dir {
+                North => self.move_up(),
+                South => self.move_down(),
+                West => self.move_left(),
+                East => self.move_right(),
             }
         }
     }
-    pub fn advance(&mut self) -> (bool, Option<Tracer<'_>>) {
+    pub fn advance(&mut self) -> (bool, Option<Tracer<'a, M>>) {
         // Simple cycle detection using position and direction
         match self.layout[self.current].redirect(self.dir) {
             (first, Some(second)) => {
                 let mut rhs = Tracer {
                     current: self.current.clone(),
                     dir: self.dir.clone(),
-                    layout: &*self.layout,
+                    layout: self.layout,
                     states: Rc::clone(&self.states),
                 };
                 let rhs = if rhs.try_move(second) {
@@ -423,18 +547,30 @@ impl Tracer<'_> {
             (first, None) => (self.try_move(first), None),
         }
     }
-    pub fn trace(&mut self) {
+    /// Trace this beam along with every beam split from it, returning the number of
+    /// beams split. Rather than recursing on each split, the beams are held on a stack.
+    /// As a beam is split only upon marking a (position, direction) state which had not
+    /// been marked, there can be at most `4 * n_rows * n_cols` beams.
+    pub fn trace(&mut self) -> usize {
+        let bound = 4 * self.layout.len();
+        let mut pending = Vec::new();
+        let mut n_beams = self.trace_one(&mut pending);
+        while let Some(mut tracer) = pending.pop() {
+            n_beams += tracer.trace_one(&mut pending);
+            assert!(n_beams <= bound, "{} beams exceeds {}", n_beams, bound);
+        }
+        n_beams
+    }
+    fn trace_one(&mut self, pending: &mut Vec<Tracer<'a, M>>) -> usize {
+        let mut n: usize = 0;
         loop {
-            match self.advance() {
-                (true, None) => (),
-                (false, None) => break,
-                (true, Some(mut branch)) => {
-                    branch.trace();
-                }
-                (false, Some(mut branch)) => {
-                    branch.trace();
-                    break;
-                }
+            let (alive, branch) = self.advance();
+            if let Some(branch) = branch {
+                pending.push(branch);
+                n += 1;
+            }
+            if !alive {
+                break n;
             }
         }
     }
@@ -488,6 +624,60 @@ mod tests {
             .fold(0u8, |acc, x| acc + x.any() as u8);
         assert_eq!(energized, 4 + 2 + 5 + 2 + 4, "\n{}", x);
     }
+    #[test]
+    fn from_str_error() {
+        let e = ".|.\n..x\n-..".parse::<Contraption>().unwrap_err();
+        assert_eq!(e, "invalid character 'x' at row 1, column 2");
+        let e = ".|.\n..\n-..".parse::<Contraption>().unwrap_err();
+        assert_eq!(e, "line 1 has length 2, expected 3");
+    }
+
+    #[test]
+    fn verify_trace() {
+        for s in [TEST, SIMPLE] {
+            let x = s.parse::<Contraption>().unwrap();
+            assert!(x.verify_trace(), "\n{}", x);
+        }
+        // A spurious mark, and a missing mark, are each detected.
+        let x = SIMPLE.parse::<Contraption>().unwrap();
+        let mut marks = x.ray_trace();
+        marks[(4, 0)].mark(North);
+        assert!(!x.verify_marks(&marks));
+        let mut marks = x.ray_trace();
+        marks[(0, 0)].reset();
+        assert!(!x.verify_marks(&marks));
+    }
+    #[test]
+    fn mark_directions() {
+        let x = SIMPLE.parse::<Contraption>().unwrap();
+        let grid = x.ray_trace();
+        assert_eq!(grid.shape(), (5, 5));
+        let mark = &grid[(0, 2)];
+        assert!(mark.is_right());
+        assert!(!mark.is_up());
+        assert!(!mark.is_down());
+        assert!(!mark.is_left());
+    }
+    #[test]
+    fn beam_stats() {
+        let x = TEST.parse::<Contraption>().unwrap();
+        let (horz, vert) = x.beam_stats();
+        let energized = x.count_energized();
+        assert!(horz <= energized && vert <= energized);
+        // Each energized cell contributes to at least one category.
+        assert!(horz + vert >= energized);
+        let both = x
+            .ray_trace()
+            .inner
+            .iter()
+            .filter(|m| (m.is_left() | m.is_right()) & (m.is_up() | m.is_down()))
+            .count();
+        assert_eq!(horz + vert - both, energized);
+
+        let x = SIMPLE.parse::<Contraption>().unwrap();
+        assert_eq!(x.beam_stats(), (10, 9));
+    }
+
     #[test]
     fn cyclic_trace() {
         let x = TEST.parse::<Contraption>().unwrap();
@@ -500,9 +690,168 @@ mod tests {
         assert_eq!(energized, 46, "\n{}", x);
     }
 
+    #[test]
+    fn packed_mark() {
+        assert_eq!(
+            std::mem::size_of::<Mark>(),
+            4 * std::mem::size_of::<PackedMark>()
+        );
+        let x = TEST.parse::<Contraption>().unwrap();
+        assert_eq!(x.count_energized_with::<PackedMark>(), 46);
+        assert_eq!(
+            x.count_energized_with::<PackedMark>(),
+            x.count_energized_with::<Mark>()
+        );
+        let packed = x.ray_trace_with::<PackedMark>();
+        let unpacked = x.ray_trace_with::<Mark>();
+        for (lhs, rhs) in packed.inner.iter().zip(unpacked.inner.iter()) {
+            for dir in [North, South, East, West] {
+                assert_eq!(lhs.is_marked(dir), rhs.is_marked(dir));
+            }
+        }
+        // A fresh contraption, so that the cache is populated by `PackedMark`.
+        let x = TEST.parse::<Contraption>().unwrap();
+        assert_eq!(x.maximum_energized_with::<PackedMark>(), 51);
+    }
+
     #[test]
     fn maximum_energized() {
         let x = TEST.parse::<Contraption>().unwrap();
         assert_eq!(x.maximum_energized(), 51);
     }
+
+    #[test]
+    fn energization_map() {
+        let x = TEST.parse::<Contraption>().unwrap();
+        let (n_rows, n_cols) = x.0.shape();
+        let v = x.energization_map();
+        assert_eq!(v.len(), 2 * (n_rows + n_cols));
+        assert_eq!(v.iter().map(|&(_, _, total)| total).max(), Some(51));
+        assert!(v.contains(&((0, 0), East, 46)));
+        assert!(v.contains(&((0, 3), South, 51)));
+        assert_eq!(x.energization_map_with::<PackedMark>(), v);
+        let x = "".parse::<Contraption>().unwrap();
+        assert!(x.energization_map().is_empty());
+    }
+
+    #[test]
+    fn count_energized_multi() {
+        let x = TEST.parse::<Contraption>().unwrap();
+        let origin = ((0, 0), East);
+        assert_eq!(x.count_energized_multi(&[origin]), 46);
+        assert_eq!(x.count_energized_multi(&[origin, origin]), 46);
+        assert_eq!(x.count_energized_multi(&[]), 0);
+        // Out of bounds seeds are ignored.
+        assert_eq!(x.count_energized_multi(&[origin, ((10, 0), North)]), 46);
+
+        let opposite = ((9, 9), West);
+        let alone = x.count_energized_multi(&[opposite]);
+        let both = x.count_energized_multi(&[origin, opposite]);
+        assert!(both >= 46 && both >= alone);
+        assert!(both <= 46 + alone);
+        assert_eq!(x.count_energized_multi(&[opposite, origin]), both);
+    }
+
+    #[test]
+    fn trace_bounded() {
+        fn trace(s: &str) -> (usize, usize) {
+            let x = s.parse::<Contraption>().unwrap();
+            let states = Rc::new(RefCell::new(Grid::<Mark>::new_default(
+                x.0.n_rows(),
+                x.0.n_cols(),
+            )));
+            let mut tracer = Tracer {
+                current: (0, 0),
+                dir: East,
+                layout: &x.0,
+                states: Rc::clone(&states),
+            };
+            let n_beams = tracer.trace();
+            assert!(n_beams <= 4 * x.0.len());
+            let energized = states.borrow().inner.iter().filter(|m| m.any()).count();
+            (n_beams, energized)
+        }
+        let n = 200;
+        let row = "|".repeat(n);
+        let s = vec![row.as_str(); n].join("\n");
+        // Split once, upward (immediately exiting) and downward along the first column.
+        assert_eq!(trace(&s), (1, n));
+
+        // Every beam is split at each element, energizing all.
+        let rows: Vec<String> = (0..n)
+            .map(|i| {
+                (0..n)
+                    .map(|j| if (i + j) % 2 == 0 { '|' } else { '-' })
+                    .collect()
+            })
+            .collect();
+        let (n_beams, energized) = trace(&rows.join("\n"));
+        assert_eq!(energized, n * n);
+        assert!(n_beams >= n * n / 2);
+    }
+
+    #[test]
+    fn single_cell() {
+        for c in ['.', '/', '\\', '|', '-'] {
+            let x = c.to_string().parse::<Contraption>().unwrap();
+            assert_eq!(x.count_energized(), 1, "{}", c);
+            assert_eq!(x.count_energized_with::<PackedMark>(), 1, "{}", c);
+            assert_eq!(x.maximum_energized(), 1, "{}", c);
+        }
+        let x = "".parse::<Contraption>().unwrap();
+        assert_eq!(x.count_energized(), 0);
+        assert_eq!(x.maximum_energized(), 0);
+    }
+
+    #[test]
+    fn energized_bounded() {
+        let elems = ['.', '/', '\\', '|', '-'];
+        for (n_rows, n_cols) in [(1, 1), (1, 2), (2, 1), (1, 3), (3, 1), (2, 2)] {
+            let n = n_rows * n_cols;
+            for mut k in 0..elems.len().pow(n as u32) {
+                let mut s = String::new();
+                for i in 0..n {
+                    if i != 0 && i % n_cols == 0 {
+                        s.push('\n');
+                    }
+                    s.push(elems[k % elems.len()]);
+                    k /= elems.len();
+                }
+                let x = s.parse::<Contraption>().unwrap();
+                let count = x.count_energized();
+                let mx = x.maximum_energized();
+                assert!(1 <= count && count <= mx && mx <= n, "{}", s);
+            }
+        }
+        let x = "|-|\n-|-\n|-|".parse::<Contraption>().unwrap();
+        assert_eq!(x.count_energized(), 9);
+        assert_eq!(x.maximum_energized(), 9);
+    }
+
+    #[test]
+    fn maximum_energized_cached() {
+        let traces = || TRACES.with(|n| n.get());
+        let mut x = TEST.parse::<Contraption>().unwrap();
+        let before = traces();
+        assert_eq!(x.maximum_energized(), 51);
+        assert_eq!(traces() - before, 40);
+        assert_eq!(x.1.borrow().len(), 40);
+        // Served entirely from the cache: no beam is traced.
+        let before = traces();
+        assert_eq!(x.maximum_energized(), 51);
+        assert_eq!(traces(), before);
+        x.invalidate_cache();
+        assert_eq!(x.1.borrow().len(), 0);
+        assert_eq!(x.maximum_energized(), 51);
+        assert_eq!(traces() - before, 40);
+
+        // Changing the layout invalidates the cache.
+        x.set(0, 1, Empty);
+        assert_eq!(x.1.borrow().len(), 0);
+        let y = TEST.replacen('|', ".", 1).parse::<Contraption>().unwrap();
+        assert_eq!(x, y);
+        let before = traces();
+        assert_eq!(x.maximum_energized(), y.maximum_energized());
+        assert_eq!(traces() - before, 80);
+    }
 }
@@ -1,66 +1,147 @@
 use crate::grid::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs;
+use std::path::Path;
 use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
+pub use crate::grid::Direction;
 use Direction::*;
 
+// As the first field is `cost`, the derived `Ord` compares by cost,
+// with the remaining fields serving only to break ties (and thus keep `Ord`
+// consistent with `Eq`). A min-heap is then `BinaryHeap<Reverse<State>>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct State {
+    cost: u16,
+    pos: (usize, usize),
+    dir: Direction,
+    run: u8,
+}
+impl State {
+    pub fn new(cost: u16, pos: (usize, usize), dir: Direction, run: u8) -> Self {
+        Self {
+            cost,
+            pos,
+            dir,
+            run,
+        }
+    }
+    pub fn cost(&self) -> u16 {
+        self.cost
+    }
+    pub fn pos(&self) -> (usize, usize) {
+        self.pos
+    }
+    pub fn dir(&self) -> Direction {
+        self.dir
+    }
+    pub fn run(&self) -> u8 {
+        self.run
+    }
+}
+
 pub struct City(Grid<u16>);
 
 impl City {
-    fn initial_direction(&self) -> Direction {
-        if self.0[(0, 1)] < self.0[(1, 0)] {
-            Right
-        } else {
-            Down
+    /// The minimal heat loss of a path from the top-left to the bottom-right block,
+    /// where the crucible must move at least `min_run` and at most `max_run` blocks
+    /// in a direction before turning (or stopping); `u16::MAX` if there is no such path.
+    ///
+    /// This is Dijkstra's algorithm on the states (position, direction, run), of which
+    /// there are at most `4 * (max_run + 1)` per block.
+    pub fn solve(&self, min_run: u8, max_run: u8) -> u16 {
+        let (n_rows, n_cols) = self.0.shape();
+        let endpoint = (n_rows - 1, n_cols - 1);
+        if endpoint == (0, 0) {
+            return 0;
+        }
+        let stride = max_run as usize + 1;
+        let index = |pos: (usize, usize), dir: Direction, run: u8| {
+            let d = match dir {
+                North => 0,
+                South => 1,
+                East => 2,
+                West => 3,
+            };
+            (self.0.linear_index(pos.0, pos.1) * 4 + d) * stride + run as usize
+        };
+        let mut best = vec![u16::MAX; self.0.len() * 4 * stride];
+        let mut heap = BinaryHeap::new();
+        for dir in [East, South] {
+            best[index((0, 0), dir, 0)] = 0;
+            heap.push(Reverse(State::new(0, (0, 0), dir, 0)));
+        }
+        while let Some(Reverse(state)) = heap.pop() {
+            if state.cost > best[index(state.pos, state.dir, state.run)] {
+                continue;
+            }
+            if state.pos == endpoint && state.run >= min_run {
+                return state.cost;
+            }
+            for dir in [state.dir, state.dir.turn_left(), state.dir.turn_right()] {
+                let run = if dir == state.dir {
+                    if state.run >= max_run {
+                        continue;
+                    }
+                    state.run + 1
+                } else if state.run >= min_run {
+                    1
+                } else {
+                    continue;
+                };
+                let (di, dj) = dir.delta();
+                let pos = match (
+                    state.pos.0.checked_add_signed(di),
+                    state.pos.1.checked_add_signed(dj),
+                ) {
+                    (Some(i), Some(j)) if i < n_rows && j < n_cols => (i, j),
+                    _ => continue,
+                };
+                let cost = state.cost.saturating_add(self.0[pos]);
+                let idx = index(pos, dir, run);
+                if cost < best[idx] {
+                    best[idx] = cost;
+                    heap.push(Reverse(State::new(cost, pos, dir, run)));
+                }
+            }
         }
+        u16::MAX
     }
+    /// The minimal heat loss of a crucible which moves at most 3 blocks in a direction.
     pub fn minimal_heat_loss(&self) -> u16 {
-        let (n_rows, n_cols) = self.0.shape();
-        let mut vis = Visitor {
-            current: (0, 0),
-            endpoint: (n_rows - 1, n_cols - 1),
-            dir: self.initial_direction(),
-            n_blocks: 0,
-            heat_loss: 0,
-            grid: &self.0,
-        };
-        vis.visit();
-        vis.heat_loss
+        self.solve(1, 3)
+    }
+    /// The minimal heat loss of an ultra crucible, which moves at least 4
+    /// and at most 10 blocks in a direction.
+    pub fn minimal_heat_loss_ultra(&self) -> u16 {
+        self.solve(4, 10)
+    }
+    pub fn from_path<T: AsRef<Path>>(path: T) -> Result<Self, String> {
+        let s = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        s.parse::<Self>()
     }
 }
 
-const OFFSET: u16 = '0' as u16;
-const BASE: u16 = 10;
 impl FromStr for City {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut v = Vec::new();
-        let mut n_rows: usize = 0;
+        let mut rows = Vec::new();
         for line in s.lines() {
-            n_rows += 1;
+            let mut row = Vec::with_capacity(line.len());
             for c in line.chars() {
-                let x = c as u16 - OFFSET;
-                if x < BASE {
-                    v.push(x);
-                } else {
-                    return Err(c.to_string());
+                match c.to_digit(10) {
+                    Some(x) => row.push(x as u16),
+                    None => return Err(c.to_string()),
                 }
             }
+            rows.push(row);
         }
-        let n = v.len();
-        let n_cols = n / n_rows;
-        if n % n_rows != 0 {
+        let grid = Grid::from_rows(rows)?;
+        if grid.len() == 0 {
             Err(s.to_string())
         } else {
-            let mut x = Grid::from_vec(v, n_cols, n_rows);
-            x.transpose_mut();
-            Ok(City(x))
+            Ok(City(grid))
         }
     }
 }
@@ -77,44 +158,49 @@ pub struct Visitor<'a> {
 
 impl Visitor<'_> {
     pub fn up(&self) -> u16 {
-        if self.current.0 == 0 {
-            u16::MAX
-        } else {
-            self.grid[(self.current.0 - 1, self.current.1)]
+        match self.current.0.checked_sub(1) {
+            Some(i) => self.heat_loss_at(i, self.current.1),
+            None => u16::MAX,
         }
     }
     pub fn down(&self) -> u16 {
-        if self.current.0 + 1 == self.grid.n_rows {
-            u16::MAX
-        } else {
-            self.grid[(self.current.0 + 1, self.current.1)]
-        }
+        self.heat_loss_at(self.current.0 + 1, self.current.1)
     }
     pub fn left(&self) -> u16 {
-        if self.current.1 == 0 {
-            u16::MAX
-        } else {
-            self.grid[(self.current.0, self.current.1 - 1)]
+        match self.current.1.checked_sub(1) {
+            Some(j) => self.heat_loss_at(self.current.0, j),
+            None => u16::MAX,
         }
     }
     pub fn right(&self) -> u16 {
-        if self.current.1 + 1 == self.grid.n_rows {
-            u16::MAX
-        } else {
-            self.grid[(self.current.0, self.current.1 + 1)]
-        }
+        self.heat_loss_at(self.current.0, self.current.1 + 1)
+    }
+    fn heat_loss_at(&self, i: usize, j: usize) -> u16 {
+        self.grid.get(i, j).copied().unwrap_or(u16::MAX)
     }
     pub fn optimal_direction(&self) -> Direction {
         if self.n_blocks < 3 {
             let (lhs, mid, rhs) = match self.dir {
-                Up => ((Left, self.left()), (Up, self.up()), (Right, self.right())),
-                Down => (
-                    (Left, self.left()),
-                    (Down, self.down()),
-                    (Right, self.right()),
+                North => (
+                    (West, self.left()),
+                    (North, self.up()),
+                    (East, self.right()),
+                ),
+                South => (
+                    (West, self.left()),
+                    (South, self.down()),
+                    (East, self.right()),
+                ),
+                West => (
+                    (South, self.down()),
+                    (West, self.left()),
+                    (North, self.up()),
+                ),
+                East => (
+                    (South, self.down()),
+                    (East, self.right()),
+                    (North, self.up()),
                 ),
-                Left => ((Down, self.down()), (Left, self.left()), (Up, self.up())),
-                Right => ((Down, self.down()), (Right, self.right()), (Up, self.up())),
             };
             let lm = lhs.1 <= mid.1;
             let mr = mid.1 <= rhs.1;
@@ -136,8 +222,8 @@ impl Visitor<'_> {
             }
         } else {
             let (lhs, rhs) = match self.dir {
-                Up | Down => ((Left, self.left()), (Right, self.right())),
-                Left | Right => ((Up, self.up()), (Down, self.down())),
+                North | South => ((West, self.left()), (East, self.right())),
+                West | East => ((North, self.up()), (South, self.down())),
             };
             if lhs.1 < rhs.1 {
                 lhs.0
@@ -147,19 +233,19 @@ impl Visitor<'_> {
         }
     }
     fn move_up(&mut self) {
-        self.dir = Up;
+        self.dir = North;
         self.current.0 -= 1;
     }
     fn move_down(&mut self) {
-        self.dir = Down;
+        self.dir = South;
         self.current.0 += 1;
     }
     fn move_left(&mut self) {
-        self.dir = Left;
+        self.dir = West;
         self.current.1 -= 1;
     }
     fn move_right(&mut self) {
-        self.dir = Right;
+        self.dir = East;
         self.current.1 += 1;
     }
     pub fn advance(&mut self) -> bool {
@@ -168,10 +254,10 @@ impl Visitor<'_> {
         } else {
             let dir = self.optimal_direction();
             match dir {
-                Up => self.move_up(),
-                Down => self.move_down(),
-                Left => self.move_left(),
-                Right => self.move_right(),
+                North => self.move_up(),
+                South => self.move_down(),
+                West => self.move_left(),
+                East => self.move_right(),
             }
             if self.n_blocks == 3 {
                 self.n_blocks = 0;
@@ -196,6 +282,17 @@ impl Visitor<'_> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn state_min_heap() {
+        let mut heap = BinaryHeap::new();
+        heap.push(Reverse(State::new(7, (0, 1), East, 1)));
+        heap.push(Reverse(State::new(2, (5, 5), North, 3)));
+        heap.push(Reverse(State::new(4, (1, 0), South, 1)));
+        heap.push(Reverse(State::new(2, (0, 0), West, 0)));
+        let costs: Vec<_> = std::iter::from_fn(|| heap.pop().map(|Reverse(x)| x.cost())).collect();
+        assert_eq!(costs, vec![2, 2, 4, 7]);
+    }
+
     static TEST: &str = "\
 2413432311323
 3215453535623
@@ -211,9 +308,71 @@ mod tests {
 2546548887735
 4322674655533";
 
-    // #[test]
-    // fn visit() {
-    //     let city = TEST.parse::<City>().unwrap();
-    //     assert_eq!(city.minimal_heat_loss(), 102);
-    // }
+    #[test]
+    fn from_str() {
+        let city = TEST.parse::<City>().unwrap();
+        assert_eq!(city.0.shape(), (13, 13));
+        assert_eq!(city.0[(0, 1)], 4);
+        assert_eq!(city.0[(1, 0)], 3);
+        let city = "12345\n67890".parse::<City>().unwrap();
+        assert_eq!(city.0.shape(), (2, 5));
+        assert_eq!(city.0[(1, 4)], 0);
+
+        assert!("12\n1a".parse::<City>().is_err());
+        // Characters below '0' must be rejected rather than underflow.
+        assert!("12\n1/".parse::<City>().is_err());
+        assert!("123\n12".parse::<City>().is_err());
+        assert!("".parse::<City>().is_err());
+    }
+
+    #[test]
+    fn visitor_bounds() {
+        // Non-square, so that confusing rows and columns is detectable.
+        let city = "12345\n67890".parse::<City>().unwrap();
+        let (n_rows, n_cols) = city.0.shape();
+        let at = |i, j| Visitor {
+            current: (i, j),
+            endpoint: (n_rows - 1, n_cols - 1),
+            dir: East,
+            n_blocks: 0,
+            heat_loss: 0,
+            grid: &city.0,
+        };
+        for i in 0..n_rows {
+            for j in 0..n_cols {
+                let vis = at(i, j);
+                assert_eq!(vis.right() == u16::MAX, j == n_cols - 1, "({}, {})", i, j);
+                assert_eq!(vis.down() == u16::MAX, i == n_rows - 1, "({}, {})", i, j);
+                assert_eq!(vis.left() == u16::MAX, j == 0, "({}, {})", i, j);
+                assert_eq!(vis.up() == u16::MAX, i == 0, "({}, {})", i, j);
+            }
+        }
+        assert_eq!(at(0, 3).right(), 5);
+        assert_eq!(at(0, 3).down(), 9);
+    }
+
+    static ULTRA: &str = "\
+111111111111
+999999999991
+999999999991
+999999999991
+999999999991";
+
+    #[test]
+    fn solve() {
+        let city = TEST.parse::<City>().unwrap();
+        assert_eq!(city.solve(1, 3), 102);
+        assert_eq!(city.solve(4, 10), 94);
+        assert_eq!(city.minimal_heat_loss(), 102);
+        assert_eq!(city.minimal_heat_loss_ultra(), 94);
+        let city = ULTRA.parse::<City>().unwrap();
+        assert_eq!(city.minimal_heat_loss_ultra(), 71);
+        // Unconstrained, the path is simply the cheapest.
+        let city = "19\n11".parse::<City>().unwrap();
+        assert_eq!(city.solve(1, 3), 2);
+        // The endpoint cannot be reached with a run of at least 4.
+        assert_eq!(city.solve(4, 10), u16::MAX);
+        let city = "5".parse::<City>().unwrap();
+        assert_eq!(city.solve(1, 3), 0);
+    }
 }
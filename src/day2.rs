@@ -1,5 +1,5 @@
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::fs;
+use std::io;
 use std::num::ParseIntError;
 use std::path::Path;
 use std::str::FromStr;
@@ -16,13 +16,27 @@ impl Game {
         r <= red && g <= green && b <= blue
     }
 
-    fn maximum_cubes(&self) -> (u8, u8, u8) {
+    /// The index of the first draw which is impossible with the given numbers of cubes.
+    pub fn first_impossible_draw(&self, red: u8, green: u8, blue: u8) -> Option<usize> {
+        self.draws
+            .iter()
+            .position(|draw| !draw.is_possible(red, green, blue))
+    }
+
+    /// The minimum set of cubes, as `(red, green, blue)`, which makes the game possible.
+    pub fn maximum_cubes(&self) -> (u8, u8, u8) {
         self.draws
             .iter()
             .fold((0, 0, 0), |(r, g, b), Draw { red, green, blue }| {
                 (r.max(*red), g.max(*green), b.max(*blue))
             })
     }
+
+    /// The product of the minimum set of cubes.
+    pub fn power(&self) -> u32 {
+        let (r, g, b) = self.maximum_cubes();
+        r as u32 * g as u32 * b as u32
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -32,6 +46,12 @@ pub struct Draw {
     blue: u8,
 }
 
+impl Draw {
+    pub fn is_possible(&self, red: u8, green: u8, blue: u8) -> bool {
+        self.red <= red && self.green <= green && self.blue <= blue
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ParseError {
     Number(ParseIntError),
@@ -123,13 +143,35 @@ pub fn sum_possible(games: &[Game], red: u8, green: u8, blue: u8) -> u32 {
 }
 
 pub fn sum_powerset(games: &[Game]) -> u32 {
-    games
-        .into_iter()
-        .map(|game| {
-            let (r, g, b) = game.maximum_cubes();
-            r as u32 * g as u32 * b as u32
-        })
-        .sum()
+    games.iter().map(Game::power).sum()
+}
+
+/// Aggregate statistics of a collection of games, for a bag of given cubes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GameStats {
+    /// The number of games which are possible.
+    pub n_possible: usize,
+    /// The sum of the ids of the games which are possible, i.e. `sum_possible`.
+    pub possible_id_sum: u32,
+    /// The sum of the power of each game, i.e. `sum_powerset`.
+    pub power_sum: u32,
+    /// The maximum number of cubes of each color seen in any game, as `(red, green, blue)`.
+    pub max_cubes: (u8, u8, u8),
+}
+
+/// Compute the `GameStats` in a single pass over the games.
+pub fn stats(games: &[Game], red: u8, green: u8, blue: u8) -> GameStats {
+    games.iter().fold(GameStats::default(), |mut acc, game| {
+        let (r, g, b) = game.maximum_cubes();
+        if r <= red && g <= green && b <= blue {
+            acc.n_possible += 1;
+            acc.possible_id_sum += game.id;
+        }
+        acc.power_sum += r as u32 * g as u32 * b as u32;
+        let (r_max, g_max, b_max) = acc.max_cubes;
+        acc.max_cubes = (r_max.max(r), g_max.max(g), b_max.max(b));
+        acc
+    })
 }
 
 /*
@@ -157,24 +199,71 @@ pub fn sum_powerset_incl_null_set(games: &[Game]) -> u32 {
         .sum()
 }
 
+pub fn games_from_str(s: &str) -> Result<Vec<Game>, ParseError> {
+    s.lines().map(|line| line.parse::<Game>()).collect()
+}
+
 pub fn games_from_file<T: AsRef<Path>>(path: T) -> Result<Vec<Game>, SumError> {
-    let f = File::open(path.as_ref())?;
-    let mut f = BufReader::new(f);
-    // 1 KiB buffer should be more than sufficient to avoid reallocations
-    let mut s = String::with_capacity(1024);
-    let mut games = Vec::new();
-    while f.read_line(&mut s)? != 0 {
-        let game = s.parse::<Game>()?;
-        games.push(game);
-        s.clear();
-    }
-    Ok(games)
+    let s = fs::read_to_string(path)?;
+    Ok(games_from_str(&s)?)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    static TEST: &str = "\
+Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
+
+    #[test]
+    fn power() {
+        let games: Vec<Game> = TEST.lines().map(|s| s.parse().unwrap()).collect();
+        assert_eq!(games[0].maximum_cubes(), (4, 2, 6));
+        assert_eq!(games[0].power(), 48);
+        assert_eq!(sum_powerset(&games), 2286);
+    }
+
+    #[test]
+    fn first_impossible_draw() {
+        let games = games_from_str(TEST).unwrap();
+        let game = &games[2];
+        assert_eq!(game.first_impossible_draw(12, 13, 14), Some(0));
+        assert_eq!(game.draws[0].red, 20);
+        assert!(!game.draws[0].is_possible(12, 13, 14));
+        assert!(game.draws[1].is_possible(12, 13, 14));
+        assert_eq!(game.first_impossible_draw(20, 13, 14), None);
+        assert_eq!(games[3].first_impossible_draw(12, 13, 14), Some(2));
+        for game in games.iter() {
+            assert_eq!(
+                game.first_impossible_draw(12, 13, 14).is_none(),
+                game.is_possible(12, 13, 14)
+            );
+        }
+    }
+
+    #[test]
+    fn games_from_str_works() {
+        let games = games_from_str(TEST).unwrap();
+        assert_eq!(games.len(), 5);
+        assert_eq!(sum_possible(&games, 12, 13, 14), 8);
+    }
+
+    #[test]
+    fn stats() {
+        let games = games_from_str(TEST).unwrap();
+        let x = super::stats(&games, 12, 13, 14);
+        assert_eq!(x.n_possible, 3);
+        assert_eq!(x.possible_id_sum, 8);
+        assert_eq!(x.power_sum, 2286);
+        assert_eq!(x.max_cubes, (20, 13, 15));
+        assert_eq!(x.possible_id_sum, sum_possible(&games, 12, 13, 14));
+        assert_eq!(super::stats(&[], 12, 13, 14), GameStats::default());
+    }
+
     #[test]
     fn draw_from_str() {
         let s = " 8 green, 6 blue, 20 red";
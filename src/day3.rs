@@ -15,7 +15,7 @@ impl Number {
 
     /// Other row means immediately above or below, i.e. if the row index is `i`, then either `i - 1` or `i + 1`
     pub fn is_adjacent_other_row(&self, j: usize) -> bool {
-        self.pos.start == j + 1 || self.pos.end == j || self.pos.contains(&j)
+        self.is_adjacent(j, false)
         // Alt 1
         // self.pos.start <= j + 1 && j < self.pos.end + 1
         // Alt 2
@@ -28,31 +28,92 @@ impl Number {
         // self.pos.start == j + 1 || self.pos.start == j || self.pos.end - 1 == j || self.pos.end == j
     }
     pub fn is_adjacent_same_row(&self, j: usize) -> bool {
-        self.pos.start == j + 1 || self.pos.end == j
+        self.is_adjacent(j, true)
     }
-    pub fn is_adjacent(&self, i_self: usize, i: usize, j: usize) -> bool {
-        if i_self == i {
-            self.is_adjacent_same_row(j)
-        } else {
-            self.is_adjacent_other_row(j)
-        }
+    /// Whether a symbol at column `j`, on the same row or the row immediately above or below,
+    /// is adjacent. The diagonal to the left is checked as `start == j + 1` rather than
+    /// `start - 1 == j`, hence, neither a number nor a symbol at column 0 underflows.
+    pub fn is_adjacent(&self, j: usize, same_row: bool) -> bool {
+        self.pos.start == j + 1 || self.pos.end == j || (!same_row && self.pos.contains(&j))
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Scan {
+pub struct Scan<F = fn(char) -> bool> {
     prev_nums: Vec<Number>,
     prev_syms: Vec<usize>,
     curr_nums: Vec<Number>,
     curr_syms: Vec<usize>,
     sum: u32,
+    is_symbol: F,
+    prev_line: Vec<Number>,
+    prev_gears: Vec<Gear>,
+    gear_sum: u32,
+}
+
+/// A `*` which is a candidate gear, along with the numbers adjacent to it thus far.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Gear {
+    col: usize,
+    n: u8,
+    ratio: u32,
+}
+impl Gear {
+    fn new(col: usize) -> Self {
+        Self {
+            col,
+            n: 0,
+            ratio: 1,
+        }
+    }
+    fn add(&mut self, value: u32) {
+        self.n = self.n.saturating_add(1);
+        if self.n <= 2 {
+            self.ratio *= value;
+        }
+    }
+    fn ratio(&self) -> Option<u32> {
+        if self.n == 2 {
+            Some(self.ratio)
+        } else {
+            None
+        }
+    }
 }
 
 const OFFSET: u32 = '0' as u32;
 
+/// Any character which is neither a digit nor `.` is a symbol.
+fn is_symbol(c: char) -> bool {
+    c != '.'
+}
+
 impl Scan {
+    pub fn new() -> Self {
+        Self::with_symbol_predicate(is_symbol)
+    }
+}
+
+impl<F: Fn(char) -> bool> Scan<F> {
+    /// Construct a scanner which counts a non-digit character as a symbol
+    /// if and only if `f` returns `true`.
+    pub fn with_symbol_predicate(f: F) -> Self {
+        Self {
+            prev_nums: Vec::new(),
+            prev_syms: Vec::new(),
+            curr_nums: Vec::new(),
+            curr_syms: Vec::new(),
+            sum: 0,
+            is_symbol: f,
+            prev_line: Vec::new(),
+            prev_gears: Vec::new(),
+            gear_sum: 0,
+        }
+    }
+
     pub fn consume_line(&mut self, s: &str) {
         // Acquire the current (from this line) numbers and symols
+        let mut curr_gears = Vec::new();
         let mut iter = s.trim_end_matches('\n').char_indices();
         while let Some((i, c)) = iter.next() {
             if c.is_ascii_digit() {
@@ -64,18 +125,51 @@ impl Scan {
                         val = val * 10 + (c as u32 - OFFSET);
                         right += 1;
                     } else {
-                        if c != '.' {
+                        if (self.is_symbol)(c) {
                             self.curr_syms.push(i);
                         }
+                        if c == '*' {
+                            curr_gears.push(Gear::new(i));
+                        }
                         break;
                     }
                 }
                 let pos = left..right;
                 self.curr_nums.push(Number::new(val, pos));
-            } else if c != '.' {
-                self.curr_syms.push(i);
+            } else {
+                if (self.is_symbol)(c) {
+                    self.curr_syms.push(i);
+                }
+                if c == '*' {
+                    curr_gears.push(Gear::new(i));
+                }
+            }
+        }
+        // Gears: current stars against previous and current numbers,
+        // then previous stars against current numbers, which completes them.
+        for gear in curr_gears.iter_mut() {
+            for num in self.prev_line.iter() {
+                if num.is_adjacent_other_row(gear.col) {
+                    gear.add(num.value);
+                }
+            }
+            for num in self.curr_nums.iter() {
+                if num.is_adjacent_same_row(gear.col) {
+                    gear.add(num.value);
+                }
+            }
+        }
+        for gear in self.prev_gears.iter_mut() {
+            for num in self.curr_nums.iter() {
+                if num.is_adjacent_other_row(gear.col) {
+                    gear.add(num.value);
+                }
             }
         }
+        self.gear_sum += self.prev_gears.iter().filter_map(Gear::ratio).sum::<u32>();
+        self.prev_gears = curr_gears;
+        self.prev_line.clear();
+        self.prev_line.extend_from_slice(&self.curr_nums);
         // Then, attempt to validate
         // Previous numbers against current symbols
         while let Some(num) = self.prev_nums.pop() {
@@ -107,44 +201,67 @@ impl Scan {
         self.prev_syms.append(&mut self.curr_syms);
     }
 
-    pub fn new() -> Self {
-        Self {
-            prev_nums: Vec::new(),
-            prev_syms: Vec::new(),
-            curr_nums: Vec::new(),
-            curr_syms: Vec::new(),
-            sum: 0,
-        }
-    }
     pub fn clear(&mut self) {
         self.prev_nums.clear();
         self.prev_syms.clear();
         self.curr_nums.clear();
         self.curr_syms.clear();
         self.sum = 0;
+        self.prev_line.clear();
+        self.prev_gears.clear();
+        self.gear_sum = 0;
+    }
+
+    /// The sum of the part numbers and the sum of the gear ratios.
+    pub fn summary(self) -> (u32, u32) {
+        let gear_sum = self.gear_sum + self.prev_gears.iter().filter_map(Gear::ratio).sum::<u32>();
+        (self.sum, gear_sum)
     }
 }
 
 pub fn sum_schematic<T: AsRef<Path>>(path: T) -> io::Result<u32> {
     let f = File::open(path.as_ref())?;
-    let mut f = BufReader::new(f);
+    sum_schematic_reader(BufReader::new(f))
+}
+
+pub fn sum_schematic_reader<R: BufRead>(mut r: R) -> io::Result<u32> {
     // 1 KiB, as usual.
     let mut s = String::with_capacity(1024);
     let mut scan = Scan::new();
-    while f.read_line(&mut s)? != 0 {
+    while r.read_line(&mut s)? != 0 {
         scan.consume_line(&s);
         s.clear();
     }
     Ok(scan.sum)
 }
 
+/// The sum of the part numbers and the sum of the gear ratios, in a single pass.
+pub fn schematic_summary<T: AsRef<Path>>(path: T) -> io::Result<(u32, u32)> {
+    let f = File::open(path.as_ref())?;
+    schematic_summary_reader(BufReader::new(f))
+}
+
+pub fn schematic_summary_reader<R: BufRead>(mut r: R) -> io::Result<(u32, u32)> {
+    // 1 KiB, as usual.
+    let mut s = String::with_capacity(1024);
+    let mut scan = Scan::new();
+    while r.read_line(&mut s)? != 0 {
+        scan.consume_line(&s);
+        s.clear();
+    }
+    Ok(scan.summary())
+}
+
 pub fn gear_sum<T: AsRef<Path>>(path: T) -> io::Result<u32> {
     let f = File::open(path.as_ref())?;
-    let mut f = BufReader::new(f);
+    gear_sum_reader(BufReader::new(f))
+}
+
+pub fn gear_sum_reader<R: BufRead>(mut r: R) -> io::Result<u32> {
     // 1 KiB, as usual.
     let mut s = String::with_capacity(1024);
     let mut scan = GearScan::new();
-    while f.read_line(&mut s)? != 0 {
+    while r.read_line(&mut s)? != 0 {
         scan.consume_line(&s);
         s.clear();
     }
@@ -218,7 +335,7 @@ impl GearScan {
                 let mut n: u8 = 0;
                 for (i_self, line) in (start..end).zip(self.lines[start..end].iter()) {
                     for num in line {
-                        if num.is_adjacent(i_self, i, j) {
+                        if num.is_adjacent(j, i_self == i) {
                             if n == 2 {
                                 return None;
                             } else if n == 0 {
@@ -296,6 +413,76 @@ mod tests {
         assert_eq!(scan.sum, 4361);
     }
 
+    #[test]
+    fn scan_symbol_predicate_works() {
+        let mut scan = Scan::with_symbol_predicate(|c| c == '*');
+        for line in TEST.lines() {
+            scan.consume_line(line);
+        }
+        assert_eq!(scan.sum, 467 + 35 + 617 + 755 + 598);
+    }
+
+    #[test]
+    fn summary() {
+        let mut scan = Scan::new();
+        for line in TEST.lines() {
+            scan.consume_line(line);
+        }
+        assert_eq!(scan.summary(), (4361, 467835));
+
+        // A gear on the final line is completed by `summary`.
+        let mut scan = Scan::new();
+        scan.consume_line("..2.....");
+        scan.consume_line(".*4..*..");
+        assert_eq!(scan.summary(), (6, 8));
+
+        use std::io::Cursor;
+        assert_eq!(
+            schematic_summary_reader(Cursor::new(TEST)).unwrap(),
+            (4361, 467835)
+        );
+    }
+
+    #[test]
+    fn is_adjacent() {
+        // Starting at column 0
+        let num = Number::new(12, 0..2);
+        for j in 0..3 {
+            assert!(num.is_adjacent(j, false));
+        }
+        assert!(!num.is_adjacent(3, false));
+        assert!(num.is_adjacent(2, true));
+        assert!(!num.is_adjacent(0, true));
+        // Symbol at column 0
+        let num = Number::new(3, 1..2);
+        assert!(num.is_adjacent(0, true));
+        assert!(num.is_adjacent(0, false));
+        let num = Number::new(3, 2..3);
+        assert!(!num.is_adjacent(0, true));
+        assert!(!num.is_adjacent(0, false));
+    }
+
+    #[test]
+    fn gear_at_boundary() {
+        for (s, ratio) in [
+            ("12......\n*.......\n3.......", 36),
+            ("12......\n*5......\n........", 60),
+            ("......12\n.......*\n.......3", 36),
+            ("........\n......5*\n......12", 60),
+            (".12.....\n*.......\n.3......", 36),
+            ("..12....\n*.......\n3.......", 0),
+        ] {
+            let mut scan = GearScan::new();
+            let mut summary = Scan::new();
+            for line in s.lines() {
+                scan.consume_line(line);
+                summary.consume_line(line);
+            }
+            assert_eq!(scan.gear_sum(), ratio, "{}", s);
+            assert_eq!(summary.summary().1, ratio, "{}", s);
+        }
+    }
+
     #[test]
     fn gear_scan_works() {
         let mut scan = GearScan::new();
@@ -304,4 +491,11 @@ mod tests {
         }
         assert_eq!(scan.gear_sum(), 467835);
     }
+
+    #[test]
+    fn reader_works() {
+        use std::io::Cursor;
+        assert_eq!(sum_schematic_reader(Cursor::new(TEST)).unwrap(), 4361);
+        assert_eq!(gear_sum_reader(Cursor::new(TEST)).unwrap(), 467835);
+    }
 }
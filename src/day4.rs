@@ -21,6 +21,17 @@ impl Card {
             win_count,
         }
     }
+    /// The numbers we have which are winning numbers, in the order we have them.
+    pub fn matches(&self) -> Vec<u32> {
+        self.have
+            .iter()
+            .filter(|x| self.winning.contains(x))
+            .cloned()
+            .collect()
+    }
+    pub fn win_count(&self) -> usize {
+        self.win_count
+    }
     pub fn points(&self) -> u64 {
         let n = self.win_count as usize;
         if n == 0 {
@@ -97,9 +108,11 @@ pub enum AcquireError {
 from_err! {ParseCardError, AcquireError, Card}
 from_err! {io::Error, AcquireError, Io}
 
-pub fn cards_from_file<T: AsRef<Path>>(path: T) -> Result<Vec<Card>, AcquireError> {
-    let f = File::open(path.as_ref())?;
-    let mut f = BufReader::new(f);
+pub fn cards_from_str(s: &str) -> Result<Vec<Card>, ParseCardError> {
+    s.lines().map(|line| line.parse::<Card>()).collect()
+}
+
+pub fn cards_from_reader<R: BufRead>(mut f: R) -> Result<Vec<Card>, AcquireError> {
     // 1 KiB, as usual
     let mut s = String::with_capacity(1024);
     let mut cards = Vec::new();
@@ -110,12 +123,22 @@ pub fn cards_from_file<T: AsRef<Path>>(path: T) -> Result<Vec<Card>, AcquireErro
     Ok(cards)
 }
 
+pub fn cards_from_file<T: AsRef<Path>>(path: T) -> Result<Vec<Card>, AcquireError> {
+    let f = File::open(path.as_ref())?;
+    cards_from_reader(BufReader::new(f))
+}
+
 pub fn count(cards: &[Card]) -> usize {
     cards.iter().map(|card| card.count_copies(cards)).sum()
 }
 
+/// The points of each card, in order.
+pub fn points_iter(cards: &[Card]) -> impl Iterator<Item = u64> + '_ {
+    cards.iter().map(Card::points)
+}
+
 pub fn sum_points(cards: &[Card]) -> u64 {
-    cards.iter().map(Card::points).sum()
+    points_iter(cards).sum()
 }
 
 #[cfg(test)]
@@ -150,9 +173,37 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
         assert_eq!(card.points(), 8);
     }
 
+    #[test]
+    fn points_iter() {
+        let cards = cards_from_str(TEST).unwrap();
+        let points: Vec<_> = super::points_iter(&cards).collect();
+        assert_eq!(points, vec![8, 2, 2, 1, 0, 0]);
+        assert_eq!(points.into_iter().sum::<u64>(), 13);
+        assert_eq!(super::points_iter(&[]).next(), None);
+    }
+
+    #[test]
+    fn matches() {
+        let cards: Vec<_> = TEST.lines().map(|s| s.parse::<Card>().unwrap()).collect();
+        assert_eq!(cards[0].matches(), vec![83, 86, 17, 48]);
+        assert_eq!(cards[0].win_count(), 4);
+        assert_eq!(cards[5].matches(), vec![]);
+        assert_eq!(cards[5].win_count(), 0);
+    }
+
     #[test]
     fn count_copies_works() {
         let cards: Vec<_> = TEST.lines().map(|s| s.parse::<Card>().unwrap()).collect();
         assert_eq!(count(&cards), 30);
     }
+
+    #[test]
+    fn cards_from_str_works() {
+        let cards = cards_from_str(TEST).unwrap();
+        assert_eq!(sum_points(&cards), 13);
+        assert_eq!(count(&cards), 30);
+
+        let other = cards_from_reader(io::Cursor::new(TEST)).unwrap();
+        assert_eq!(cards, other);
+    }
 }
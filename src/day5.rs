@@ -1,10 +1,15 @@
+use std::fmt;
 use std::fs;
+use std::num::ParseIntError;
+use std::ops::Range;
 use std::path::Path;
 use std::str::FromStr;
 
+// Values are `u64` rather than `usize`, as the inputs contain values in excess
+// of `u32::MAX`, which would overflow on 32-bit targets.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Almanac {
-    seeds: Vec<usize>,
+    seeds: Vec<u64>,
     seed_to_soil: Map,
     soil_to_fertilizer: Map,
     fertilizer_to_water: Map,
@@ -14,39 +19,76 @@ pub struct Almanac {
     humidity_to_location: Map,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseAlmanacError {
+    MissingSeeds,
+    WrongMapCount(usize),
+    UnexpectedGarden {
+        expected: (Garden, Garden),
+        found: (Garden, Garden),
+    },
+    UnknownGarden(String),
+    Malformed(String),
+    Int(ParseIntError),
+}
+
+macro_rules! from_err {
+    {$T:path, $U:path, $V:ident} => {
+        impl From<$T> for $U {
+            fn from(e: $T) -> Self {
+                Self::$V(e)
+            }
+        }
+    }
+}
+from_err! {ParseIntError, ParseAlmanacError, Int}
+
+impl fmt::Display for ParseAlmanacError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ParseAlmanacError::*;
+        match self {
+            MissingSeeds => write!(f, "almanac does not begin with seeds"),
+            WrongMapCount(n) => write!(f, "almanac has {} maps, expected 7", n),
+            UnexpectedGarden { expected, found } => write!(
+                f,
+                "expected {:?}-to-{:?} map, found {:?}-to-{:?} map",
+                expected.0, expected.1, found.0, found.1
+            ),
+            UnknownGarden(s) => write!(f, "unknown garden: {}", s),
+            Malformed(s) => write!(f, "malformed: {}", s),
+            Int(e) => write!(f, "{}", e),
+        }
+    }
+}
+
 impl FromStr for Almanac {
-    type Err = String;
+    type Err = ParseAlmanacError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut iter = s.split("\n\n");
-        let seeds = if let Some(line) = iter.next() {
-            if let Some((lhs, rhs)) = line.split_once(':') {
-                if lhs != "seeds" {
-                    return Err(s.to_string());
-                } else {
-                    let mut seeds = Vec::new();
-                    let rhs = rhs.trim();
-                    for num in rhs.split_whitespace() {
-                        seeds.push(num.parse::<usize>().map_err(|e| e.to_string())?);
-                    }
-                    seeds
+        let seeds = match iter.next().and_then(|line| line.split_once(':')) {
+            Some(("seeds", rhs)) => {
+                let mut seeds = Vec::new();
+                for num in rhs.split_whitespace() {
+                    seeds.push(num.parse::<u64>()?);
                 }
-            } else {
-                return Err(s.to_string());
+                seeds
             }
-        } else {
-            return Err(s.to_string());
+            _ => return Err(ParseAlmanacError::MissingSeeds),
         };
         let mut maps = Vec::with_capacity(7);
         for block in iter {
             maps.push(block.parse::<Map>()?);
         }
         if maps.len() != 7 {
-            Err(s.to_string())
+            Err(ParseAlmanacError::WrongMapCount(maps.len()))
         } else {
             macro_rules! err_if_not {
                 ($x:ident, $src:ident, $dst:ident) => {
                     if !$x.has_src_dst(&Garden::$src, &Garden::$dst) {
-                        return Err(s.to_string());
+                        return Err(ParseAlmanacError::UnexpectedGarden {
+                            expected: (Garden::$src, Garden::$dst),
+                            found: ($x.src, $x.dst),
+                        });
                     }
                 };
             }
@@ -79,7 +121,28 @@ impl FromStr for Almanac {
 }
 
 impl Almanac {
-    pub fn location(&self, seed: usize) -> usize {
+    fn maps(&self) -> [&Map; 7] {
+        [
+            &self.seed_to_soil,
+            &self.soil_to_fertilizer,
+            &self.fertilizer_to_water,
+            &self.water_to_light,
+            &self.light_to_temperature,
+            &self.temperature_to_humidity,
+            &self.humidity_to_location,
+        ]
+    }
+
+    /// Check that no map contains overlapping source ranges, as these would
+    /// render `lookup` dependent on the order of the ranges.
+    pub fn validate(&self) -> Result<(), String> {
+        for map in self.maps() {
+            map.validate()?;
+        }
+        Ok(())
+    }
+
+    pub fn location(&self, seed: u64) -> u64 {
         let soil = self.seed_to_soil.lookup(seed);
         let fertilizer = self.soil_to_fertilizer.lookup(soil);
         let water = self.fertilizer_to_water.lookup(fertilizer);
@@ -89,23 +152,23 @@ impl Almanac {
         self.humidity_to_location.lookup(humidity)
     }
 
-    pub fn locations_part1(&self) -> impl Iterator<Item = usize> + '_ {
+    pub fn locations_part1(&self) -> impl Iterator<Item = u64> + '_ {
         self.seeds.iter().map(|&seed| self.location(seed))
     }
 
-    pub fn minimum_location<'a, F, T>(&'a self, f: F) -> usize
+    pub fn minimum_location<'a, F, T>(&'a self, f: F) -> u64
     where
         F: Fn(&'a Almanac) -> T,
-        T: Iterator<Item = usize> + 'a,
+        T: Iterator<Item = u64> + 'a,
     {
-        f(&self).fold(usize::MAX, |acc, x| acc.min(x))
+        f(self).fold(u64::MAX, |acc, x| acc.min(x))
     }
 
-    pub fn minimum_location_part1(&self) -> usize {
+    pub fn minimum_location_part1(&self) -> u64 {
         self.minimum_location(|x| x.locations_part1())
     }
 
-    pub fn locations_part2(&self) -> impl Iterator<Item = usize> + '_ {
+    pub fn locations_part2(&self) -> impl Iterator<Item = u64> + '_ {
         assert_eq!(self.seeds.len() & 1, 0);
         self.seeds.chunks_exact(2).flat_map(|w| {
             let start = w[0];
@@ -113,17 +176,84 @@ impl Almanac {
             (start..start + len).map(|seed| self.location(seed))
         })
     }
-    pub fn minimum_location_part2(&self) -> usize {
+    pub fn minimum_location_part2(&self) -> u64 {
         self.minimum_location(|x| x.locations_part2())
     }
 
+    /// The location ranges corresponding to the seed ranges of part 2,
+    /// obtained by mapping each range (rather than each seed) through the maps.
+    pub fn location_ranges_part2(&self) -> Vec<Range<u64>> {
+        assert_eq!(self.seeds.len() & 1, 0);
+        let mut ranges: Vec<_> = self
+            .seeds
+            .chunks_exact(2)
+            .map(|w| w[0]..w[0] + w[1])
+            .filter(|r| !r.is_empty())
+            .collect();
+        for map in self.maps() {
+            ranges = ranges
+                .into_iter()
+                .flat_map(|r| map.lookup_range(r))
+                .collect();
+        }
+        ranges
+    }
+    /// The location ranges of part 2, sorted and with overlapping or adjacent
+    /// ranges merged, i.e. a compact description of the reachable locations.
+    pub fn location_ranges(&self) -> Vec<Range<u64>> {
+        let mut ranges = self.location_ranges_part2();
+        ranges.sort_unstable_by_key(|r| r.start);
+        let mut merged: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+        for r in ranges {
+            match merged.last_mut() {
+                Some(last) if r.start <= last.end => last.end = last.end.max(r.end),
+                _ => merged.push(r),
+            }
+        }
+        merged
+    }
+    /// The exact minimum location of part 2, without visiting each seed.
+    pub fn minimum_location_sorted(&self) -> u64 {
+        let mut ranges = self.location_ranges_part2();
+        ranges.sort_unstable_by_key(|r| r.start);
+        ranges.first().map_or(u64::MAX, |r| r.start)
+    }
+
+    /// Compose the seven maps into a single seed-to-location map, such that
+    /// `self.compose().lookup(seed) == self.location(seed)`; assumes the
+    /// source ranges of each map are disjoint.
+    pub fn compose(&self) -> Map {
+        // Pieces of the seed domain, each with the start of its image.
+        let mut pieces: Vec<(Range<u64>, u64)> = vec![(0..u64::MAX, 0)];
+        for map in self.maps() {
+            let mut next = Vec::with_capacity(pieces.len());
+            for (src, dst) in pieces {
+                // `lookup_range` yields the images in order of the values
+                // from which they originate.
+                let mut start = src.start;
+                for r in map.lookup_range(dst..dst + (src.end - src.start)) {
+                    let end = start + (r.end - r.start);
+                    next.push((start..end, r.start));
+                    start = end;
+                }
+            }
+            pieces = next;
+        }
+        let ranges = pieces
+            .into_iter()
+            .filter(|(src, dst)| src.start != *dst)
+            .map(|(src, dst)| SrcDst::new(src.start, dst, src.end - src.start))
+            .collect();
+        Map::new(ranges, Garden::Seed, Garden::Location)
+    }
+
     pub fn from_path<T: AsRef<Path>>(path: T) -> Result<Self, String> {
         let s = fs::read_to_string(path.as_ref()).map_err(|e| e.to_string())?;
-        s.parse::<Self>()
+        s.parse::<Self>().map_err(|e| e.to_string())
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Garden {
     Seed,
     Soil,
@@ -135,18 +265,8 @@ pub enum Garden {
     Location,
 }
 
-// macro_rules! from_err {
-//     {$T:path, $U:path, $V:ident} => {
-//         impl From<$T> for $U {
-//             fn from(e: $T) -> Self {
-//                 Self::$V(e)
-//             }
-//         }
-//     }
-// }
-
 impl FromStr for Garden {
-    type Err = String;
+    type Err = ParseAlmanacError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         use Garden::*;
         match s {
@@ -158,7 +278,7 @@ impl FromStr for Garden {
             "temperature" => Ok(Temperature),
             "humidity" => Ok(Humidity),
             "location" => Ok(Location),
-            _ => Err(s.to_string()),
+            _ => Err(ParseAlmanacError::UnknownGarden(s.to_string())),
         }
     }
 }
@@ -187,7 +307,7 @@ pub struct Map {
 }
 
 impl FromStr for Map {
-    type Err = String;
+    type Err = ParseAlmanacError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if let Some((lhs, rhs)) = s.split_once(':') {
             let lhs = lhs.trim_end_matches(" map");
@@ -201,10 +321,10 @@ impl FromStr for Map {
                 }
                 Ok(Map::new(ranges, src, dst))
             } else {
-                Err(s.to_string())
+                Err(ParseAlmanacError::Malformed(s.to_string()))
             }
         } else {
-            Err(s.to_string())
+            Err(ParseAlmanacError::Malformed(s.to_string()))
         }
     }
 }
@@ -239,7 +359,7 @@ impl Map {
     user	1m10.729s
     sys	0m0.005s
     */
-    pub fn lookup(&self, i: usize) -> usize {
+    pub fn lookup(&self, i: u64) -> u64 {
         match self.ranges.binary_search_by(|x| x.src.cmp(&i)) {
             Ok(mid) => match self.ranges[mid].lookup(i) {
                 Some(j) => j,
@@ -252,7 +372,7 @@ impl Map {
             },
         }
     }
-    // pub fn lookup(&self, i: usize) -> usize {
+    // pub fn lookup(&self, i: u64) -> u64 {
     //     // With a bit more effort, this could be converted to a binary search,
     //     // hence, O(lgn) rather than O(n).
     //     for srcdst in self.ranges.iter() {
@@ -264,39 +384,121 @@ impl Map {
     //     i
     // }
 
+    /// Map a range of values to the (possibly several) ranges of values
+    /// to which they correspond; assumes the source ranges are disjoint.
+    pub fn lookup_range(&self, r: Range<u64>) -> Vec<Range<u64>> {
+        let mut v = Vec::new();
+        let mut start = r.start;
+        for x in self.ranges.iter() {
+            if start >= r.end || x.src >= r.end {
+                break;
+            }
+            let end = x.src + x.len;
+            if end <= start {
+                continue;
+            }
+            // The unmapped gap preceding this source range.
+            if start < x.src {
+                v.push(start..x.src);
+                start = x.src;
+            }
+            let stop = end.min(r.end);
+            let offset = start - x.src;
+            v.push(x.dst + offset..x.dst + offset + (stop - start));
+            start = stop;
+        }
+        if start < r.end {
+            v.push(start..r.end);
+        }
+        v
+    }
+
     pub fn has_src_dst(&self, src: &Garden, dst: &Garden) -> bool {
         self.src == *src && self.dst == *dst
     }
+
+    /// Sort the ranges by source and merge those which continue one another
+    /// (adjacent in both source and destination); overlapping source ranges
+    /// are an error, and leave the map unchanged.
+    pub fn normalize(&mut self) -> Result<(), String> {
+        self.ranges.sort_unstable_by_key(|x| x.src);
+        self.validate()?;
+        let mut merged: Vec<SrcDst> = Vec::with_capacity(self.ranges.len());
+        for x in self.ranges.drain(..) {
+            match merged.last_mut() {
+                Some(last) if last.src + last.len == x.src && last.dst + last.len == x.dst => {
+                    last.len += x.len
+                }
+                _ => merged.push(x),
+            }
+        }
+        self.ranges = merged;
+        Ok(())
+    }
+
+    /// Check that the source ranges are disjoint.
+    pub fn validate(&self) -> Result<(), String> {
+        // `ranges` is sorted by `src`, thus, it suffices to check neighbors.
+        for w in self.ranges.windows(2) {
+            if w[0].src + w[0].len > w[1].src {
+                return Err(format!(
+                    "{:?}-to-{:?} map has overlapping source ranges: {:?}, {:?}",
+                    self.src, self.dst, w[0], w[1]
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SrcDst {
-    src: usize,
-    dst: usize,
-    len: usize,
+    src: u64,
+    dst: u64,
+    len: u64,
 }
 
 impl FromStr for SrcDst {
-    type Err = String;
+    type Err = ParseAlmanacError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut iter = s.split_whitespace();
         match (iter.next(), iter.next(), iter.next()) {
             (Some(dst), Some(src), Some(len)) => {
-                let len = len.parse::<usize>().map_err(|e| e.to_string())?;
-                let dst = dst.parse::<usize>().map_err(|e| e.to_string())?;
-                let src = src.parse::<usize>().map_err(|e| e.to_string())?;
+                let len = len.parse::<u64>()?;
+                let dst = dst.parse::<u64>()?;
+                let src = src.parse::<u64>()?;
                 Ok(Self { src, dst, len })
             }
-            _ => Err(s.to_string()),
+            _ => Err(ParseAlmanacError::Malformed(s.to_string())),
         }
     }
 }
 
 impl SrcDst {
-    pub fn new(src: usize, dst: usize, len: usize) -> Self {
+    pub fn new(src: u64, dst: u64, len: u64) -> Self {
         Self { src, dst, len }
     }
-    pub fn lookup(&self, i: usize) -> Option<usize> {
+    pub fn src(&self) -> u64 {
+        self.src
+    }
+    pub fn dst(&self) -> u64 {
+        self.dst
+    }
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// The values which this maps.
+    pub fn src_range(&self) -> Range<u64> {
+        self.src..self.src + self.len
+    }
+    /// The values to which this maps.
+    pub fn dst_range(&self) -> Range<u64> {
+        self.dst..self.dst + self.len
+    }
+    pub fn lookup(&self, i: u64) -> Option<u64> {
         let j = i.wrapping_sub(self.src);
         if j >= self.len {
             None
@@ -328,14 +530,14 @@ pub struct LightTemperature;
 pub struct TemperatureHumidity;
 pub struct HumidityLocation;
 
-pub struct Seed(usize);
-pub struct Soil(usize);
-pub struct Fertilizer(usize);
-pub struct Water(usize);
-pub struct Light(usize);
-pub struct Temperature(usize);
-pub struct Humidity(usize);
-pub struct Location(usize);
+pub struct Seed(u64);
+pub struct Soil(u64);
+pub struct Fertilizer(u64);
+pub struct Water(u64);
+pub struct Light(u64);
+pub struct Temperature(u64);
+pub struct Humidity(u64);
+pub struct Location(u64);
 
 macro_rules! impl_relation {
     { $($T:ident)+ } => {
@@ -373,7 +575,7 @@ impl<T: Relation + FromStr<Err = String>> FromStr for Map2<T> {
             let mut ranges = Vec::new();
             let rhs = rhs.trim();
             for line in rhs.lines() {
-                ranges.push(line.parse::<SrcDst>()?);
+                ranges.push(line.parse::<SrcDst>().map_err(|e| e.to_string())?);
             }
             Ok(Map2::new(ranges, marker))
         } else {
@@ -386,7 +588,7 @@ impl<T: Relation> Map2<T> {
     pub fn new(ranges: Vec<SrcDst>, _marker: T) -> Self {
         Self { ranges, _marker }
     }
-    pub fn lookup_imp(&self, i: usize) -> usize {
+    pub fn lookup_imp(&self, i: u64) -> u64 {
         match self.ranges.binary_search_by(|x| x.src.cmp(&i)) {
             Ok(mid) => match self.ranges[mid].lookup(i) {
                 Some(j) => j,
@@ -419,7 +621,7 @@ impl_lookup! { TemperatureHumidity, Temperature, Humidity}
 impl_lookup! { HumidityLocation, Humidity, Location }
 
 pub struct Almanac2 {
-    seeds: Vec<usize>,
+    seeds: Vec<u64>,
     seed_to_soil: Map2<SeedSoil>,
     soil_to_fertilizer: Map2<SoilFertilizer>,
     fertilizer_to_water: Map2<FertilizerWater>,
@@ -441,7 +643,7 @@ impl FromStr for Almanac2 {
                     let mut seeds = Vec::new();
                     let rhs = rhs.trim();
                     for num in rhs.split_whitespace() {
-                        seeds.push(num.parse::<usize>().map_err(|e| e.to_string())?);
+                        seeds.push(num.parse::<u64>().map_err(|e| e.to_string())?);
                     }
                     seeds
                 }
@@ -480,7 +682,7 @@ impl FromStr for Almanac2 {
 }
 
 impl Almanac2 {
-    pub fn location(&self, seed: Seed) -> usize {
+    pub fn location(&self, seed: Seed) -> u64 {
         // This sequence is now guaranteed through the use of the typestate pattern.
         // If someone were to mistakenly change the order, it would not longer compile.
         let soil = self.seed_to_soil.lookup(seed);
@@ -492,23 +694,23 @@ impl Almanac2 {
         self.humidity_to_location.lookup(humidity).0
     }
 
-    pub fn locations_part1(&self) -> impl Iterator<Item = usize> + '_ {
+    pub fn locations_part1(&self) -> impl Iterator<Item = u64> + '_ {
         self.seeds.iter().map(|&seed| self.location(Seed(seed)))
     }
 
-    pub fn minimum_location<'a, F, T>(&'a self, f: F) -> usize
+    pub fn minimum_location<'a, F, T>(&'a self, f: F) -> u64
     where
         F: Fn(&'a Almanac2) -> T,
-        T: Iterator<Item = usize> + 'a,
+        T: Iterator<Item = u64> + 'a,
     {
-        f(&self).fold(usize::MAX, |acc, x| acc.min(x))
+        f(self).fold(u64::MAX, |acc, x| acc.min(x))
     }
 
-    pub fn minimum_location_part1(&self) -> usize {
+    pub fn minimum_location_part1(&self) -> u64 {
         self.minimum_location(|x| x.locations_part1())
     }
 
-    pub fn locations_part2(&self) -> impl Iterator<Item = usize> + '_ {
+    pub fn locations_part2(&self) -> impl Iterator<Item = u64> + '_ {
         assert_eq!(self.seeds.len() & 1, 0);
         self.seeds.chunks_exact(2).flat_map(|w| {
             let start = w[0];
@@ -516,7 +718,7 @@ impl Almanac2 {
             (start..start + len).map(|seed| self.location(Seed(seed)))
         })
     }
-    pub fn minimum_location_part2(&self) -> usize {
+    pub fn minimum_location_part2(&self) -> u64 {
         self.minimum_location(|x| x.locations_part2())
     }
 
@@ -585,6 +787,19 @@ humidity-to-location map:
         assert_eq!(x.lookup(81), Some(74));
     }
 
+    #[test]
+    fn srcdst_accessors() {
+        let x = SrcDst::new(98, 50, 2);
+        assert_eq!((x.src(), x.dst(), x.len()), (98, 50, 2));
+        assert!(!x.is_empty());
+        assert_eq!(x.src_range(), 98..100);
+        assert_eq!(x.dst_range(), 50..52);
+        for i in x.src_range() {
+            assert!(x.dst_range().contains(&x.lookup(i).unwrap()));
+        }
+        assert!(SrcDst::new(1, 2, 0).src_range().is_empty());
+    }
+
     #[test]
     fn map_lookup() {
         let map = Map::new(
@@ -624,6 +839,47 @@ humidity-to-location map:
         assert_eq!(map.lookup(81), 74);
     }
 
+    #[test]
+    fn map_normalize() {
+        let mut map = Map::new(
+            vec![SrcDst::new(98, 50, 2), SrcDst::new(50, 52, 48)],
+            Garden::Seed,
+            Garden::Soil,
+        );
+        let orig = map.clone();
+        assert!(map.normalize().is_ok());
+        assert_eq!(map, orig);
+
+        // Continuations are merged, without changing any lookup.
+        let mut map = Map::new(
+            vec![
+                SrcDst::new(10, 100, 5),
+                SrcDst::new(15, 105, 5),
+                SrcDst::new(20, 0, 5),
+            ],
+            Garden::Seed,
+            Garden::Soil,
+        );
+        let orig = map.clone();
+        assert!(map.normalize().is_ok());
+        assert_eq!(
+            map.ranges,
+            vec![SrcDst::new(10, 100, 10), SrcDst::new(20, 0, 5)]
+        );
+        for i in 0..30 {
+            assert_eq!(map.lookup(i), orig.lookup(i));
+        }
+
+        let mut map = Map::new(
+            vec![SrcDst::new(10, 100, 6), SrcDst::new(15, 105, 5)],
+            Garden::Seed,
+            Garden::Soil,
+        );
+        let orig = map.clone();
+        assert!(map.normalize().is_err());
+        assert_eq!(map, orig);
+    }
+
     #[test]
     fn map_from_str() {
         let s = "\
@@ -646,6 +902,22 @@ seed-to-soil map:
         assert_eq!(x.seeds, vec![79, 14, 55, 13]);
     }
 
+    #[test]
+    fn almanac_validate() {
+        let x = TEST.parse::<Almanac>().unwrap();
+        assert!(x.validate().is_ok());
+
+        let s = TEST.replace("52 50 48", "52 50 49");
+        let x = s.parse::<Almanac>().unwrap();
+        let e = x.validate().unwrap_err();
+        assert!(e.starts_with("Seed-to-Soil"), "{}", e);
+
+        let s = TEST.replace("0 69 1", "0 68 2");
+        let x = s.parse::<Almanac>().unwrap();
+        let e = x.validate().unwrap_err();
+        assert!(e.starts_with("Temperature-to-Humidity"), "{}", e);
+    }
+
     #[test]
     fn locations_part1() {
         let x = TEST.parse::<Almanac>().unwrap();
@@ -664,4 +936,138 @@ seed-to-soil map:
         let x = TEST.parse::<Almanac>().unwrap();
         assert_eq!(x.minimum_location_part2(), 46);
     }
+
+    #[test]
+    fn map_lookup_range() {
+        let map = Map::new(
+            vec![SrcDst::new(98, 50, 2), SrcDst::new(50, 52, 48)],
+            Garden::Seed,
+            Garden::Soil,
+        );
+        assert_eq!(map.lookup_range(0..10), vec![0..10]);
+        assert_eq!(map.lookup_range(45..55), vec![45..50, 52..57]);
+        assert_eq!(map.lookup_range(96..102), vec![98..100, 50..52, 100..102]);
+        assert_eq!(map.lookup_range(120..130), vec![120..130]);
+        for r in [0..1, 40..60, 49..99, 0..200] {
+            let mut lhs: Vec<_> = map.lookup_range(r.clone()).into_iter().flatten().collect();
+            let mut rhs: Vec<_> = r.map(|i| map.lookup(i)).collect();
+            lhs.sort_unstable();
+            rhs.sort_unstable();
+            assert_eq!(lhs, rhs);
+        }
+    }
+
+    #[test]
+    fn compose() {
+        let x = TEST.parse::<Almanac>().unwrap();
+        let map = x.compose();
+        assert!(map.has_src_dst(&Garden::Seed, &Garden::Location));
+        assert!(map.validate().is_ok());
+        for seed in x.seeds.iter().cloned().chain(0..200) {
+            assert_eq!(map.lookup(seed), x.location(seed), "seed: {}", seed);
+        }
+    }
+
+    #[test]
+    fn minimum_location_sorted() {
+        let x = TEST.parse::<Almanac>().unwrap();
+        assert_eq!(x.minimum_location_sorted(), 46);
+
+        // The exact minimum never exceeds (and hence equals) the brute-force minimum.
+        let mut x = x;
+        for start in (0..100).step_by(7) {
+            for len in (1..30).step_by(4) {
+                for other in [0, 13, 57] {
+                    x.seeds = vec![start, len, other, len + 2];
+                    assert_eq!(
+                        x.minimum_location_sorted(),
+                        x.minimum_location_part2(),
+                        "{:?}",
+                        x.seeds
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn location_ranges() {
+        let mut x = TEST.parse::<Almanac>().unwrap();
+        let ranges = x.location_ranges();
+        assert_eq!(ranges[0].start, 46);
+        assert!(ranges.windows(2).all(|w| w[0].end < w[1].start));
+
+        // Overlapping seed ranges yield overlapping outputs, which are coalesced.
+        x.seeds = vec![79, 14, 80, 10, 55, 13];
+        assert!(x.location_ranges_part2().len() > x.location_ranges().len());
+        let ranges = x.location_ranges();
+        assert!(ranges.windows(2).all(|w| w[0].end < w[1].start));
+        let mut lhs: Vec<_> = ranges.into_iter().flatten().collect();
+        let mut rhs: Vec<_> = x.locations_part2().collect();
+        rhs.sort_unstable();
+        rhs.dedup();
+        lhs.sort_unstable();
+        assert_eq!(lhs, rhs);
+    }
+
+    #[test]
+    fn location_beyond_u32() {
+        let s = "\
+seeds: 5000000000 10 4294967295 2
+
+seed-to-soil map:
+6000000000 5000000000 10
+
+soil-to-fertilizer map:
+
+fertilizer-to-water map:
+
+water-to-light map:
+
+light-to-temperature map:
+
+temperature-to-humidity map:
+
+humidity-to-location map:
+0 6000000005 5
+4294967296 4294967295 1";
+        let x = s.parse::<Almanac>().unwrap();
+        assert_eq!(x.location(5_000_000_003), 6_000_000_003);
+        assert_eq!(x.location(5_000_000_007), 2);
+        assert_eq!(x.location(u32::MAX as u64), 1 << 32);
+        assert_eq!(x.location(1 << 32), 1 << 32);
+        assert_eq!(x.minimum_location_part1(), 2);
+        assert_eq!(x.minimum_location_part2(), 0);
+        assert_eq!(x.minimum_location_sorted(), 0);
+    }
+
+    #[test]
+    fn parse_almanac_error() {
+        let (truncated, _) = TEST.rsplit_once("\n\n").unwrap();
+        assert_eq!(
+            truncated.parse::<Almanac>(),
+            Err(ParseAlmanacError::WrongMapCount(6))
+        );
+
+        let mislabeled = TEST.replace("water-to-light", "water-to-soil");
+        assert_eq!(
+            mislabeled.parse::<Almanac>(),
+            Err(ParseAlmanacError::UnexpectedGarden {
+                expected: (Garden::Water, Garden::Light),
+                found: (Garden::Water, Garden::Soil),
+            })
+        );
+
+        let unseeded = TEST.replacen("seeds", "sneeds", 1);
+        assert_eq!(
+            unseeded.parse::<Almanac>(),
+            Err(ParseAlmanacError::MissingSeeds)
+        );
+        let e = TEST.replacen("79", "7x", 1).parse::<Almanac>().unwrap_err();
+        assert!(matches!(e, ParseAlmanacError::Int(_)));
+        assert_eq!(
+            "water-to-lite map:\n1 2 3".parse::<Map>(),
+            Err(ParseAlmanacError::UnknownGarden("lite".to_string()))
+        );
+    }
 }
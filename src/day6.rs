@@ -1,3 +1,4 @@
+use std::io::Read;
 use std::{fs, path::Path, str::FromStr};
 
 /*
@@ -68,6 +69,12 @@ impl Race {
     /// N.B. this is Newton's method of tangents, not the secant method
     /// (which could also be a way to solve this).
     pub fn ways_to_win_newton(&self) -> u64 {
+        self.winning_bounds().map_or(0, |(lb, ub)| ub - lb + 1)
+    }
+    /// The inclusive bounds `(min_charge, max_charge)` of the charge times which
+    /// beat the record, or `None` if the record cannot be beaten;
+    /// see `ways_to_win_newton` for the method.
+    pub fn winning_bounds(&self) -> Option<(u64, u64)> {
         #[inline]
         fn g(x: i128, t_r: i128, d_best: i128) -> i128 {
             // Wrapping is intended
@@ -93,12 +100,13 @@ impl Race {
         fn obj(x: i128, t_r: i128) -> i128 {
             x * t_r - x * x
         }
-        // We need to explicitly protect against zero values
-        if self.t_r == 0 || self.d_best == 0 {
-            0
+        let t_r = self.t_r as i128;
+        let d_best = self.d_best as i128;
+        // If the best charge time does not beat the record, none will; this also
+        // guarantees distinct roots, hence, that the iterations avoid `dg == 0`.
+        if obj(t_r / 2, t_r) <= d_best {
+            None
         } else {
-            let t_r = self.t_r.clone() as i128;
-            let d_best = self.d_best.clone() as i128;
             // lower bound
             let lb = {
                 let mut lb = newton(0, t_r, d_best);
@@ -116,7 +124,7 @@ impl Race {
                 ub
             };
 
-            (ub - lb + 1) as u64
+            Some((lb as u64, ub as u64))
         }
     }
 }
@@ -151,6 +159,24 @@ pub fn parse_races_part1(s: &str) -> Result<Vec<Race>, String> {
     }
 }
 
+/// How to combine the number of ways to win each race.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combine {
+    Sum,
+    Product,
+}
+
+pub fn combine_ways(races: &[Race], how: Combine) -> u64 {
+    let ways = races.iter().map(Race::ways_to_win_bracketing);
+    match how {
+        Combine::Sum => ways.sum(),
+        Combine::Product => ways.product(),
+    }
+}
+pub fn product_of_ways(races: &[Race]) -> u64 {
+    combine_ways(races, Combine::Product)
+}
+
 pub fn races_from_path_part1<T: AsRef<Path>>(path: T) -> Result<Vec<Race>, String> {
     let s = fs::read_to_string(path.as_ref()).map_err(|e| e.to_string())?;
     parse_races_part1(&s)
@@ -207,6 +233,12 @@ impl Race {
         let s = fs::read_to_string(path.as_ref()).map_err(|e| e.to_string())?;
         s.parse::<Self>()
     }
+    /// As `from_path`, but reading from any source, e.g. stdin.
+    pub fn from_reader<R: Read>(mut r: R) -> Result<Self, String> {
+        let mut s = String::new();
+        r.read_to_string(&mut s).map_err(|e| e.to_string())?;
+        s.parse::<Self>()
+    }
 }
 
 #[cfg(test)]
@@ -265,6 +297,23 @@ Distance:  9  40  200";
         assert_eq!(x.ways_to_win_newton(), 71503);
     }
 
+    #[test]
+    fn winning_bounds() {
+        assert_eq!(Race::new(7, 9).winning_bounds(), Some((2, 5)));
+        assert_eq!(Race::new(30, 200).winning_bounds(), Some((11, 19)));
+        assert_eq!(Race::new(4, 4).winning_bounds(), None);
+        assert_eq!(Race::new(4, 4).ways_to_win_newton(), 0);
+        assert_eq!(Race::new(0, 0).winning_bounds(), None);
+        assert_eq!(Race::new(1, 0).winning_bounds(), None);
+        assert_eq!(Race::new(5, 0).winning_bounds(), Some((1, 4)));
+        for t_r in 0..40 {
+            for d_best in 0..(t_r * t_r / 4 + 3) {
+                let x = Race::new(t_r, d_best);
+                assert_eq!(x.ways_to_win_newton() as usize, x.ways_to_win(), "{:?}", x);
+            }
+        }
+    }
+
     #[test]
     fn parse_races_part1_works() {
         let lhs = parse_races_part1(TEST).unwrap();
@@ -274,9 +323,24 @@ Distance:  9  40  200";
         );
     }
 
+    #[test]
+    fn product_of_ways_works() {
+        let races = parse_races_part1(TEST).unwrap();
+        assert_eq!(product_of_ways(&races), 4 * 8 * 9);
+        assert_eq!(product_of_ways(&races), 288);
+        assert_eq!(combine_ways(&races, Combine::Sum), 4 + 8 + 9);
+    }
+
     #[test]
     fn from_str() {
         let x = TEST.parse::<Race>().unwrap();
         assert_eq!(x, Race::new(71530, 940200));
     }
+
+    #[test]
+    fn from_reader() {
+        let x = Race::from_reader(std::io::Cursor::new(TEST)).unwrap();
+        assert_eq!(x, Race::new(71530, 940200));
+        assert!(Race::from_reader(std::io::Cursor::new("Time: 7")).is_err());
+    }
 }
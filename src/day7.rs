@@ -1,7 +1,7 @@
 use std::convert::TryFrom;
-use std::fs;
 use std::path::Path;
 use std::str::FromStr;
+use std::{fmt, fs};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Card {
@@ -21,6 +21,24 @@ pub enum Card {
     A,
 }
 
+/// Which rules to play by: part 1 treats `J` as a jack, part 2 as a joker.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Rules {
+    #[default]
+    Standard,
+    Joker,
+}
+
+impl Card {
+    /// The relative strength of the card under the given rules; jokers are the weakest.
+    pub fn strength(&self, rules: Rules) -> u8 {
+        match (self, rules) {
+            (Card::J, Rules::Joker) => 0,
+            _ => *self as u8 + 1,
+        }
+    }
+}
+
 impl FromStr for Card {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -67,6 +85,32 @@ impl TryFrom<char> for Card {
     }
 }
 
+impl From<Card> for char {
+    fn from(card: Card) -> char {
+        use Card::*;
+        match card {
+            Two => '2',
+            Three => '3',
+            Four => '4',
+            Five => '5',
+            Six => '6',
+            Seven => '7',
+            Eight => '8',
+            Nine => '9',
+            T => 'T',
+            J => 'J',
+            Q => 'Q',
+            K => 'K',
+            A => 'A',
+        }
+    }
+}
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", char::from(*self))
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum HandType {
     HighCard,
@@ -77,9 +121,9 @@ pub enum HandType {
     FourOfAKind,
     FiveOfAKind,
 }
-fn classify(cards: &[Card; 5]) -> HandType {
+/// The number of cards of each rank, indexed from `Two` to `A`.
+fn count_cards(cards: &[Card; 5]) -> [u8; 13] {
     use Card::*;
-    use HandType::*;
     let mut count = [0u8; 13];
     for card in cards {
         match card {
@@ -98,8 +142,20 @@ fn classify(cards: &[Card; 5]) -> HandType {
             A => count[12] += 1,
         }
     }
+    count
+}
+
+/// The five largest of the rank counts from `count_cards`, in ascending order.
+fn count_signature(mut count: [u8; 13]) -> [u8; 5] {
     count.sort_unstable();
-    match count[8..13] {
+    let mut signature = [0u8; 5];
+    signature.copy_from_slice(&count[8..13]);
+    signature
+}
+
+fn classify(cards: &[Card; 5]) -> HandType {
+    use HandType::*;
+    match count_signature(count_cards(cards)) {
         [1, 1, 1, 1, 1] => HighCard,
         [0, 1, 1, 1, 2] => OnePair,
         [0, 0, 1, 2, 2] => TwoPair,
@@ -111,47 +167,28 @@ fn classify(cards: &[Card; 5]) -> HandType {
     }
 }
 
-#[allow(dead_code)]
 fn classify_wildcard(cards: &[Card; 5]) -> HandType {
-    use Card::*;
     use HandType::*;
-    let mut count = [0u8; 13];
-    for card in cards {
-        match card {
-            Two => count[0] += 1,
-            Three => count[1] += 1,
-            Four => count[2] += 1,
-            Five => count[3] += 1,
-            Six => count[4] += 1,
-            Seven => count[5] += 1,
-            Eight => count[6] += 1,
-            Nine => count[7] += 1,
-            T => count[8] += 1,
-            J => count[9] += 1,
-            Q => count[10] += 1,
-            K => count[11] += 1,
-            A => count[12] += 1,
-        }
-    }
-    let n = count[9].clone();
-    count.sort_unstable();
+    let count = count_cards(cards);
+    let n = count[9];
+    let signature = count_signature(count);
     if n == 5 || n == 4 {
         FiveOfAKind
     } else if n == 3 {
-        match count[8..13] {
+        match signature {
             [0, 0, 1, 1, 3] => FourOfAKind,
             [0, 0, 0, 2, 3] => FiveOfAKind,
             _ => unreachable!(),
         }
     } else if n == 2 {
-        match count[8..13] {
+        match signature {
             [0, 1, 1, 1, 2] => ThreeOfAKind,
             [0, 0, 1, 2, 2] => FourOfAKind,
             [0, 0, 0, 2, 3] => FiveOfAKind,
             _ => unreachable!(),
         }
     } else if n == 1 {
-        match count[8..13] {
+        match signature {
             [1, 1, 1, 1, 1] => OnePair,
             [0, 1, 1, 1, 2] => ThreeOfAKind,
             [0, 0, 1, 2, 2] => FullHouse,
@@ -160,7 +197,7 @@ fn classify_wildcard(cards: &[Card; 5]) -> HandType {
             _ => unreachable!(),
         }
     } else {
-        match count[8..13] {
+        match signature {
             [1, 1, 1, 1, 1] => HighCard,
             [0, 1, 1, 1, 2] => OnePair,
             [0, 0, 1, 2, 2] => TwoPair,
@@ -183,6 +220,31 @@ pub struct Hand {
     cards: [Card; 5],
 }
 
+impl Hand {
+    pub fn hand_type(&self) -> HandType {
+        self.ty
+    }
+    pub fn cards(&self) -> &[Card; 5] {
+        &self.cards
+    }
+    /// The number of cards of each rank, indexed from `Two` to `A`.
+    pub fn card_counts(&self) -> [u8; 13] {
+        count_cards(&self.cards)
+    }
+    /// The number of distinct ranks among the cards.
+    pub fn distinct_ranks(&self) -> usize {
+        self.card_counts().iter().filter(|&&n| n != 0).count()
+    }
+    /// The sort key of the hand under the given rules.
+    pub fn key(&self, rules: Rules) -> (HandType, [u8; 5]) {
+        let ty = match rules {
+            Rules::Standard => self.ty,
+            Rules::Joker => classify_wildcard(&self.cards),
+        };
+        (ty, self.cards.map(|card| card.strength(rules)))
+    }
+}
+
 impl From<[Card; 5]> for Hand {
     fn from(cards: [Card; 5]) -> Self {
         // let ty = classify_wildcard(&cards); // for part 2
@@ -206,6 +268,15 @@ impl FromStr for Hand {
     }
 }
 
+impl fmt::Display for Hand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for card in self.cards.iter() {
+            write!(f, "{}", card)?;
+        }
+        Ok(())
+    }
+}
+
 pub fn parse_hand_bids(s: &str) -> Result<Vec<(Hand, u64)>, String> {
     let mut v = Vec::new();
     for line in s.lines() {
@@ -220,13 +291,22 @@ pub fn parse_hand_bids(s: &str) -> Result<Vec<(Hand, u64)>, String> {
     Ok(v)
 }
 pub fn total_winnings(v: &mut Vec<(Hand, u64)>) -> u64 {
-    v.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    total_winnings_with(v, Rules::Standard)
+}
+pub fn total_winnings_with(v: &mut [(Hand, u64)], rules: Rules) -> u64 {
+    v.sort_unstable_by_key(|x| x.0.key(rules));
     v.iter()
         .map(|x| x.1)
         .zip(1u64..)
         .fold(0u64, |acc, (rank, bid)| rank * bid + acc)
 }
 
+/// Parse the hands and bids, then compute the total winnings under the given rules.
+pub fn solve_from_str(s: &str, rules: Rules) -> Result<u64, String> {
+    let mut v = parse_hand_bids(s)?;
+    Ok(total_winnings_with(&mut v, rules))
+}
+
 pub fn hand_bids_from_path<T: AsRef<Path>>(path: T) -> Result<Vec<(Hand, u64)>, String> {
     let s = fs::read_to_string(path.as_ref()).map_err(|e| e.to_string())?;
     parse_hand_bids(&s)
@@ -276,6 +356,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn count_signature_works() {
+        assert_eq!(
+            count_signature(count_cards(&[A, A, Eight, A, A])),
+            [0, 0, 0, 1, 4]
+        );
+        assert_eq!(
+            count_signature(count_cards(&[Two, Three, Four, Five, Six])),
+            [1, 1, 1, 1, 1]
+        );
+        assert_eq!(
+            count_signature(count_cards(&[K, K, Six, Seven, Seven])),
+            [0, 0, 1, 2, 2]
+        );
+        assert_eq!(
+            count_signature(count_cards(&[J, J, J, J, J])),
+            [0, 0, 0, 0, 5]
+        );
+    }
+
     #[test]
     fn classify_works() {
         let cards = [A, A, A, A, A];
@@ -338,6 +438,30 @@ mod tests {
         assert_eq!(lhs, rhs);
     }
 
+    #[test]
+    fn card_counts() {
+        let h = "AA8AA".parse::<Hand>().unwrap();
+        let counts = h.card_counts();
+        assert_eq!(counts[A as usize], 4);
+        assert_eq!(counts[Eight as usize], 1);
+        assert_eq!(counts.iter().sum::<u8>(), 5);
+        assert_eq!(h.distinct_ranks(), 2);
+
+        assert_eq!("32T3K".parse::<Hand>().unwrap().distinct_ranks(), 4);
+        assert_eq!("23456".parse::<Hand>().unwrap().distinct_ranks(), 5);
+        assert_eq!("JJJJJ".parse::<Hand>().unwrap().distinct_ranks(), 1);
+    }
+
+    #[test]
+    fn hand_to_string() {
+        assert_eq!("KTJJT".parse::<Hand>().unwrap().to_string(), "KTJJT");
+        assert_eq!(Hand::from([A, Two, Nine, T, Q]).to_string(), "A29TQ");
+        for c in "23456789TJQKA".chars() {
+            let card = Card::try_from(c).unwrap();
+            assert_eq!(card.to_string().parse::<Card>(), Ok(card));
+        }
+    }
+
     #[test]
     fn hand_ord() {
         let lhs = Hand::from([Three, Three, Three, Three, Two]);
@@ -349,6 +473,24 @@ mod tests {
         assert_eq!(lhs.cmp(&rhs), std::cmp::Ordering::Greater);
     }
 
+    #[test]
+    fn hand_tie_break() {
+        let lhs = "77888".parse::<Hand>().unwrap();
+        let rhs = "77788".parse::<Hand>().unwrap();
+        assert_eq!(lhs.hand_type(), FullHouse);
+        assert_eq!(rhs.hand_type(), FullHouse);
+        assert_eq!(lhs.cards(), &[Seven, Seven, Eight, Eight, Eight]);
+        // Same type, hence, the first differing card decides.
+        let (l, r) = lhs
+            .cards()
+            .iter()
+            .zip(rhs.cards().iter())
+            .find(|(l, r)| l != r)
+            .unwrap();
+        assert_eq!((*l, *r), (Eight, Seven));
+        assert_eq!(lhs.cmp(&rhs), l.cmp(r));
+    }
+
     #[test]
     fn total_winnings_works() {
         let mut v = vec![
@@ -380,6 +522,19 @@ QQQJA 483";
         assert_eq!(lhs, rhs);
     }
 
+    #[test]
+    fn solve_from_str_works() {
+        static TEST: &str = "\
+32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483";
+        assert_eq!(solve_from_str(TEST, Rules::Standard), Ok(6440));
+        assert_eq!(solve_from_str(TEST, Rules::Joker), Ok(5905));
+        assert!(solve_from_str("32T3K", Rules::Standard).is_err());
+    }
+
     #[test]
     fn classify_wildcard_works() {
         let cards = [T, Five, Five, J, Five];
@@ -1,5 +1,7 @@
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fs;
+use std::io::Read;
 use std::path::Path;
 use std::str::FromStr;
 
@@ -39,6 +41,19 @@ impl From<Vec<Instruction>> for InstructionSeq {
         Self(insns)
     }
 }
+impl InstructionSeq {
+    /// Compress runs of identical instructions into `(instruction, run length)`.
+    pub fn run_length(&self) -> Vec<(Instruction, usize)> {
+        let mut v: Vec<(Instruction, usize)> = Vec::new();
+        for &insn in self.0.iter() {
+            match v.last_mut() {
+                Some((last, n)) if *last == insn => *n += 1,
+                _ => v.push((insn, 1)),
+            }
+        }
+        v
+    }
+}
 
 // #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 // pub struct Node([char; 3]);
@@ -240,6 +255,33 @@ impl Network {
         }
     }
 
+    /// As `traverse`, but on success, returns the nodes visited, in order,
+    /// from `entry` to `exit` (inclusive).
+    pub fn traverse_path(
+        &self,
+        seq: InstructionSeq,
+        entry: Node,
+        exit: Node,
+    ) -> Result<Vec<Node>, usize> {
+        let seq = seq.0;
+        if !seq.is_empty() {
+            let mut seq = seq.into_iter().cycle();
+
+            let mut path = vec![entry];
+            let mut n: usize = 0;
+            while let Some(next) = self.branch(seq.next().unwrap(), &path[n]) {
+                n += 1;
+                path.push(next.clone());
+                if *next == exit {
+                    return Ok(path);
+                }
+            }
+            Err(n)
+        } else {
+            Err(0)
+        }
+    }
+
     pub fn simultaneous_traverse(&self, seq: InstructionSeq) -> Result<usize, usize> {
         let seq = seq.0;
         if !seq.is_empty() {
@@ -269,18 +311,211 @@ impl Network {
             Err(0)
         }
     }
+
+    /// The steps at which a ghost starting from `entry` stands on a node ending in `Z`,
+    /// found by walking until a (node, instruction position) state repeats.
+    fn z_times(&self, seq: &[Instruction], entry: &Node) -> Option<ZTimes> {
+        let mut seen = HashMap::new();
+        let mut times = Vec::new();
+        let mut node = entry;
+        let mut t: usize = 0;
+        loop {
+            let pos = t % seq.len();
+            if let Some(&offset) = seen.get(&(node.idx, pos)) {
+                let period = t - offset;
+                let split = times.partition_point(|&x| x < offset);
+                let residues = times[split..].iter().map(|x| x % period).collect();
+                times.truncate(split);
+                return Some(ZTimes {
+                    transient: times,
+                    offset,
+                    period,
+                    residues,
+                });
+            }
+            seen.insert((node.idx, pos), t);
+            if node.ends_with_z() {
+                times.push(t);
+            }
+            node = self.branch(seq[pos], node)?;
+            t += 1;
+        }
+    }
+
+    /// As `simultaneous_traverse`, but rather than stepping every ghost in lockstep,
+    /// detects the cycle of each ghost and the positions of the `Z` nodes within it,
+    /// then solves the resulting system of congruences by the Chinese Remainder Theorem.
+    /// Unlike the least common multiple of the first arrival times, this is correct
+    /// when a ghost reaches a `Z` node before entering its cycle, when the offset of
+    /// a `Z` node differs from the cycle length, or when a cycle holds several `Z` nodes.
+    ///
+    /// Should the number of residue combinations be excessive (or the moduli overflow),
+    /// falls back to `simultaneous_traverse`.
+    ///
+    /// Interpretation of `Result<usize, usize>`:
+    /// - Ok(n)  : all ghosts simultaneously stand on `Z` nodes after `n` branches
+    /// - Err(0) : cannot traverse with empty `seq`
+    /// - Err(n) : n > 0; no such step exists, as determined after examining `n` branches
+    pub fn simultaneous_traverse_crt(&self, seq: InstructionSeq) -> Result<usize, usize> {
+        const MAX_COMBINATIONS: usize = 1 << 16;
+        if seq.0.is_empty() {
+            return Err(0);
+        }
+        let mut ghosts = Vec::new();
+        let mut examined: usize = 0;
+        for tree in self.trees.iter().filter(|x| x.id.ends_with_a()) {
+            match self.z_times(&seq.0, &tree.id) {
+                Some(z) => {
+                    examined = examined.max(z.offset + z.period);
+                    ghosts.push(z);
+                }
+                None => return self.simultaneous_traverse(seq),
+            }
+        }
+        // A step prior to the cycle of some ghost must be checked individually.
+        let mut best = ghosts
+            .iter()
+            .flat_map(|z| z.transient.iter().copied())
+            .filter(|&t| ghosts.iter().all(|z| z.contains(t)))
+            .min();
+
+        let n_combinations = ghosts
+            .iter()
+            .try_fold(1usize, |acc, z| acc.checked_mul(z.residues.len()));
+        match n_combinations {
+            Some(0) => (),
+            Some(n) if n <= MAX_COMBINATIONS => {
+                let start = ghosts.iter().map(|z| z.offset).max().unwrap_or(0) as u128;
+                let mut idx = vec![0usize; ghosts.len()];
+                for _ in 0..n {
+                    let mut acc = Some((0u128, 1u128));
+                    for (z, &i) in ghosts.iter().zip(idx.iter()) {
+                        acc = match acc {
+                            Some((a, m)) => {
+                                match crt(a, m, z.residues[i] as u128, z.period as u128) {
+                                    Congruence::Solution(a, m) => Some((a, m)),
+                                    Congruence::Incompatible => None,
+                                    Congruence::Overflow => return self.simultaneous_traverse(seq),
+                                }
+                            }
+                            None => None,
+                        };
+                    }
+                    if let Some((a, m)) = acc {
+                        // The smallest t >= start such that t = a (mod m).
+                        let t = if a >= start {
+                            a
+                        } else {
+                            a + (start - a).div_ceil(m) * m
+                        };
+                        match usize::try_from(t) {
+                            Ok(t) => best = Some(best.map_or(t, |b| b.min(t))),
+                            Err(_) => return self.simultaneous_traverse(seq),
+                        }
+                    }
+                    // Advance to the next combination of residues.
+                    for (z, i) in ghosts.iter().zip(idx.iter_mut()) {
+                        *i += 1;
+                        if *i < z.residues.len() {
+                            break;
+                        }
+                        *i = 0;
+                    }
+                }
+            }
+            _ => return self.simultaneous_traverse(seq),
+        }
+        best.ok_or(examined.max(1))
+    }
 }
 
-pub fn seq_network_from_path<T: AsRef<Path>>(path: T) -> Result<(InstructionSeq, Network), String> {
-    let s = fs::read_to_string(path.as_ref()).map_err(|e| e.to_string())?;
+/// The steps at which a single ghost stands on a node ending in `Z`: those prior
+/// to entering its cycle, and the residues (modulo `period`) of those on the cycle,
+/// which begins at step `offset`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ZTimes {
+    transient: Vec<usize>,
+    offset: usize,
+    period: usize,
+    residues: Vec<usize>,
+}
+impl ZTimes {
+    fn contains(&self, t: usize) -> bool {
+        if t < self.offset {
+            self.transient.contains(&t)
+        } else {
+            self.residues.contains(&(t % self.period))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Congruence {
+    Solution(u128, u128),
+    Incompatible,
+    Overflow,
+}
+
+/// Returns (x, y, g) such that a * x + b * y = g = gcd(a, b).
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (1, 0, a)
+    } else {
+        let (x, y, g) = extended_gcd(b, a % b);
+        (y, x - (a / b) * y, g)
+    }
+}
+
+/// Combine t = a1 (mod m1) and t = a2 (mod m2), where the moduli need not be coprime,
+/// into t = a (mod lcm(m1, m2)), if such a t exists.
+fn crt(a1: u128, m1: u128, a2: u128, m2: u128) -> Congruence {
+    let (Ok(a1), Ok(m1), Ok(a2), Ok(m2)) = (
+        i128::try_from(a1),
+        i128::try_from(m1),
+        i128::try_from(a2),
+        i128::try_from(m2),
+    ) else {
+        return Congruence::Overflow;
+    };
+    let (x, _, g) = extended_gcd(m1, m2);
+    let diff = a2 - a1;
+    if diff % g != 0 {
+        return Congruence::Incompatible;
+    }
+    let m2_g = m2 / g;
+    let Some(lcm) = m1.checked_mul(m2_g) else {
+        return Congruence::Overflow;
+    };
+    // m1 * k = diff (mod m2)  =>  k = (diff / g) * x (mod m2 / g)
+    let Some(k) = ((diff / g) % m2_g).checked_mul(x % m2_g) else {
+        return Congruence::Overflow;
+    };
+    let k = k.rem_euclid(m2_g);
+    match m1.checked_mul(k).and_then(|y| y.checked_add(a1)) {
+        Some(a) => Congruence::Solution(a.rem_euclid(lcm) as u128, lcm as u128),
+        None => Congruence::Overflow,
+    }
+}
+
+pub fn seq_network_from_str(s: &str) -> Result<(InstructionSeq, Network), String> {
     if let Some((lhs, rhs)) = s.split_once("\n\n") {
         let lhs = lhs.parse::<InstructionSeq>()?;
         let rhs = rhs.parse::<Network>()?;
         Ok((lhs, rhs))
     } else {
-        Err(s)
+        Err(s.to_string())
     }
 }
+/// As `seq_network_from_path`, but reading from any source, e.g. stdin.
+pub fn seq_network_from_reader<R: Read>(mut r: R) -> Result<(InstructionSeq, Network), String> {
+    let mut s = String::new();
+    r.read_to_string(&mut s).map_err(|e| e.to_string())?;
+    seq_network_from_str(&s)
+}
+pub fn seq_network_from_path<T: AsRef<Path>>(path: T) -> Result<(InstructionSeq, Network), String> {
+    let s = fs::read_to_string(path.as_ref()).map_err(|e| e.to_string())?;
+    seq_network_from_str(&s)
+}
 
 #[cfg(test)]
 mod tests {
@@ -294,12 +529,36 @@ mod tests {
     ZZZ = (ZZZ, ZZZ)
     */
 
+    #[test]
+    fn seq_network_from_reader() {
+        let s = "\
+LLR
+
+AAA = (BBB, BBB)
+BBB = (AAA, ZZZ)
+ZZZ = (ZZZ, ZZZ)";
+        let (seq, network) = super::seq_network_from_reader(std::io::Cursor::new(s)).unwrap();
+        let entry = Node::from(['A', 'A', 'A']);
+        let exit = Node::from(['Z', 'Z', 'Z']);
+        assert_eq!(network.traverse(seq, entry, exit), Ok(6));
+        assert!(super::seq_network_from_str("LLR").is_err());
+    }
+
     #[test]
     fn instruction_seq_from_str() {
         let lhs = "LLRRL".parse::<InstructionSeq>().unwrap();
         assert_eq!(lhs, InstructionSeq(vec![L, L, R, R, L]));
     }
 
+    #[test]
+    fn run_length() {
+        let lhs = "LLLRRL".parse::<InstructionSeq>().unwrap();
+        assert_eq!(lhs.run_length(), vec![(L, 3), (R, 2), (L, 1)]);
+        let lhs = "R".parse::<InstructionSeq>().unwrap();
+        assert_eq!(lhs.run_length(), vec![(R, 1)]);
+        assert_eq!(InstructionSeq::from(vec![]).run_length(), vec![]);
+    }
+
     #[test]
     fn node_from_str() {
         let lhs = "XYZ".parse::<Node>().unwrap();
@@ -358,6 +617,40 @@ ZZZ = (ZZZ, ZZZ)";
         assert_eq!(network.traverse(inst_set, entry, exit).unwrap(), 6);
     }
 
+    #[test]
+    fn traverse_path_works() {
+        let s = "\
+AAA = (BBB, BBB)
+BBB = (AAA, ZZZ)
+ZZZ = (ZZZ, ZZZ)";
+        let network = s.parse::<Network>().unwrap();
+        let entry = "AAA".parse::<Node>().unwrap();
+        let exit = "ZZZ".parse::<Node>().unwrap();
+        let path = network
+            .traverse_path(InstructionSeq(vec![L, L, R]), entry.clone(), exit.clone())
+            .unwrap();
+        assert_eq!(path.len(), 7);
+        assert_eq!(path.first(), Some(&entry));
+        assert_eq!(path.last(), Some(&exit));
+        let bbb = "BBB".parse::<Node>().unwrap();
+        assert_eq!(
+            path,
+            vec![
+                entry.clone(),
+                bbb.clone(),
+                entry.clone(),
+                bbb.clone(),
+                entry,
+                bbb,
+                exit.clone()
+            ]
+        );
+        assert_eq!(
+            network.traverse_path(InstructionSeq(vec![]), exit.clone(), exit),
+            Err(0)
+        );
+    }
+
     #[test]
     fn simultaneous_traverse_works() {
         let s = "\
@@ -374,5 +667,61 @@ XXX = (XXX, XXX)";
             .simultaneous_traverse(InstructionSeq(vec![L, R]))
             .unwrap();
         assert_eq!(lhs, 6);
+        let lhs = network
+            .simultaneous_traverse_crt(InstructionSeq(vec![L, R]))
+            .unwrap();
+        assert_eq!(lhs, 6);
+    }
+
+    #[test]
+    fn simultaneous_traverse_crt() {
+        // The first ghost enters a cycle of length 3 after one step and is at PPZ
+        // at steps 2, 5, 8, ...; the second has a cycle of length 4 and is at QQZ
+        // at steps 3, 7, 11, .... The least common multiple of the first arrivals
+        // (6), or of the cycle lengths (12), is wrong; t = 2 (mod 3), t = 3 (mod 4)
+        // gives 11.
+        let s = "\
+PPA = (PPB, PPB)
+PPB = (PPZ, PPZ)
+PPZ = (PPC, PPC)
+PPC = (PPB, PPB)
+QQA = (QQB, QQB)
+QQB = (QQC, QQC)
+QQC = (QQZ, QQZ)
+QQZ = (QQA, QQA)";
+        let network = s.parse::<Network>().unwrap();
+        let seq = InstructionSeq(vec![L]);
+        assert_eq!(network.simultaneous_traverse(seq.clone()), Ok(11));
+        assert_eq!(network.simultaneous_traverse_crt(seq), Ok(11));
+
+        // Moduli which are not coprime, with a ghost which passes a `Z` node
+        // only before entering its cycle.
+        let s = "\
+PPA = (PPZ, PPZ)
+PPZ = (PPB, PPB)
+PPB = (PPC, PPC)
+PPC = (PPB, PPB)
+QQA = (QQZ, QQZ)
+QQZ = (QQB, QQB)
+QQB = (QQA, QQA)";
+        let network = s.parse::<Network>().unwrap();
+        let seq = InstructionSeq(vec![L, R]);
+        assert_eq!(network.simultaneous_traverse_crt(seq), Ok(1));
+
+        // t = 0 (mod 2) and t = 1 (mod 2) are incompatible.
+        let s = "\
+PPA = (PPZ, PPZ)
+PPZ = (PPA, PPA)
+QQA = (QQB, QQB)
+QQB = (QQZ, QQZ)
+QQZ = (QQB, QQB)";
+        let network = s.parse::<Network>().unwrap();
+        assert!(network
+            .simultaneous_traverse_crt(InstructionSeq(vec![L]))
+            .is_err());
+        assert_eq!(
+            network.simultaneous_traverse_crt(InstructionSeq(vec![])),
+            Err(0)
+        );
     }
 }
@@ -1,5 +1,4 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
 use std::path::Path;
 
 /* Simple but inefficient due to many unnecessary allocations */
@@ -56,9 +55,15 @@ fn inv_diff_in_place(x: &mut [i32]) {
         }
     }
 }
+/// Extrapolate the next value of the sequence.
+///
+/// A single-element sequence extrapolates to that element; an empty
+/// sequence returns `0`.
 pub fn extrapolate_fwd(v: &mut [i32]) -> i32 {
     let n = v.len();
-    if n > 1 {
+    if n == 1 {
+        v[0]
+    } else if n > 1 {
         let last = v[n - 1].clone();
         diff_in_place(v);
         if v[1..].iter().all(|x| *x == 0) {
@@ -73,9 +78,15 @@ pub fn extrapolate_fwd(v: &mut [i32]) -> i32 {
         0
     }
 }
+/// Extrapolate the previous value of the sequence.
+///
+/// A single-element sequence extrapolates to that element; an empty
+/// sequence returns `0`.
 pub fn extrapolate_back(v: &mut [i32]) -> i32 {
     let n = v.len();
-    if n > 1 {
+    if n == 1 {
+        v[0]
+    } else if n > 1 {
         diff_in_place(v);
         if v[1..].iter().all(|x| *x == 0) {
             inv_diff_in_place(v);
@@ -89,6 +100,47 @@ pub fn extrapolate_back(v: &mut [i32]) -> i32 {
         0
     }
 }
+/// Extrapolate both the next and the previous value of the sequence,
+/// performing the difference cascade only once.
+pub fn extrapolate_both(v: &mut [i32]) -> (i32, i32) {
+    let n = v.len();
+    if n == 1 {
+        (v[0], v[0])
+    } else if n > 1 {
+        let last = v[n - 1];
+        diff_in_place(v);
+        if v[1..].iter().all(|x| *x == 0) {
+            inv_diff_in_place(v);
+            (last, v[0])
+        } else {
+            let (fwd, back) = extrapolate_both(&mut v[1..]);
+            let first = v[0] - back;
+            inv_diff_in_place(v);
+            (last + fwd, first)
+        }
+    } else {
+        (0, 0)
+    }
+}
+
+/// The degree of the polynomial fitted by the extrapolation, i.e. the number of
+/// differences taken before the sequence becomes constant; the sequence is restored.
+///
+/// An empty or constant sequence has degree `0`.
+pub fn polynomial_degree(v: &mut [i32]) -> usize {
+    if v.len() > 1 {
+        diff_in_place(v);
+        let degree = if v[1..].iter().all(|x| *x == 0) {
+            0
+        } else {
+            1 + polynomial_degree(&mut v[1..])
+        };
+        inv_diff_in_place(v);
+        degree
+    } else {
+        0
+    }
+}
 
 pub fn parse_line(s: &str) -> Result<Vec<i32>, String> {
     let mut v = Vec::new();
@@ -98,20 +150,22 @@ pub fn parse_line(s: &str) -> Result<Vec<i32>, String> {
     Ok(v)
 }
 
+/// The forward and backward extrapolated values of each line, in order.
+pub fn extrapolate_lines(s: &str) -> Result<Vec<(i32, i32)>, String> {
+    let mut v = Vec::new();
+    for line in s.lines() {
+        v.push(extrapolate_both(&mut parse_line(line)?));
+    }
+    Ok(v)
+}
+
 pub fn sum_extrapolated_from_path<T: AsRef<Path>>(path: T) -> Result<(i32, i32), String> {
-    let f = File::open(path.as_ref()).map_err(|e| e.to_string())?;
-    let mut f = BufReader::new(f);
-    let mut s = String::with_capacity(1024);
-    let mut sum_fwd: i32 = 0;
-    let mut sum_back: i32 = 0;
-    while f.read_line(&mut s).map_err(|e| e.to_string())? != 0 {
-        s.pop();
-        let mut v = parse_line(&s)?;
-        sum_fwd += extrapolate_fwd(&mut v);
-        sum_back += extrapolate_back(&mut v);
-        s.clear();
-    }
-    Ok((sum_fwd, sum_back))
+    let s = fs::read_to_string(path.as_ref()).map_err(|e| e.to_string())?;
+    Ok(extrapolate_lines(&s)?
+        .into_iter()
+        .fold((0, 0), |(sum_fwd, sum_back), (fwd, back)| {
+            (sum_fwd + fwd, sum_back + back)
+        }))
 }
 
 #[cfg(test)]
@@ -142,6 +196,59 @@ mod tests {
         assert_eq!(extrapolate_back(&mut v), 5);
     }
 
+    #[test]
+    fn extrapolate_both_works() {
+        let mut v = vec![10, 13, 16, 21, 30, 45];
+        assert_eq!(extrapolate_both(&mut v), (68, 5));
+        assert_eq!(v, vec![10, 13, 16, 21, 30, 45]);
+
+        for mut v in [vec![0, 3, 6, 9, 12, 15], vec![1, 3, 6, 10, 15, 21], vec![7]] {
+            let fwd = extrapolate_fwd(&mut v);
+            let back = extrapolate_back(&mut v);
+            assert_eq!(extrapolate_both(&mut v), (fwd, back));
+        }
+    }
+
+    #[test]
+    fn extrapolate_lines_works() {
+        let s = "\
+0 3 6 9 12 15
+1 3 6 10 15 21
+10 13 16 21 30 45";
+        assert_eq!(
+            extrapolate_lines(s).unwrap(),
+            vec![(18, -3), (28, 0), (68, 5)]
+        );
+        assert!(extrapolate_lines("1 2 x").is_err());
+    }
+
+    #[test]
+    fn extrapolate_degenerate() {
+        let mut v = vec![5];
+        assert_eq!(extrapolate_fwd(&mut v), 5);
+        assert_eq!(extrapolate_back(&mut v), 5);
+        assert_eq!(v, vec![5]);
+
+        let mut v: Vec<i32> = vec![];
+        assert_eq!(extrapolate_fwd(&mut v), 0);
+        assert_eq!(extrapolate_back(&mut v), 0);
+    }
+
+    #[test]
+    fn polynomial_degree() {
+        let mut v = vec![0, 3, 6, 9, 12, 15];
+        assert_eq!(super::polynomial_degree(&mut v), 1);
+        assert_eq!(v, vec![0, 3, 6, 9, 12, 15]);
+        let mut v = vec![1, 3, 6, 10, 15, 21];
+        assert_eq!(super::polynomial_degree(&mut v), 2);
+        assert_eq!(v, vec![1, 3, 6, 10, 15, 21]);
+        let mut v = vec![10, 13, 16, 21, 30, 45];
+        assert_eq!(super::polynomial_degree(&mut v), 3);
+        assert_eq!(super::polynomial_degree(&mut [7, 7, 7]), 0);
+        assert_eq!(super::polynomial_degree(&mut [7]), 0);
+        assert_eq!(super::polynomial_degree(&mut []), 0);
+    }
+
     #[test]
     fn diff_in_place_works() {
         let mut v = vec![0, 3, 6, 9, 12, 15];
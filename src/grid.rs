@@ -5,6 +5,79 @@ use std::fmt;
 use std::ops::{Index, IndexMut};
 use std::str::FromStr;
 
+/// A cardinal direction on the grid; north is toward row 0, west is toward column 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+}
+use Direction::*;
+
+/// The variant names used by the per-day enums which this replaced, so that
+/// paths such as `Direction::Up` continue to work, including in patterns.
+#[allow(non_upper_case_globals)]
+impl Direction {
+    pub const Up: Self = North;
+    pub const Down: Self = South;
+    pub const Left: Self = West;
+    pub const Right: Self = East;
+    pub const Top: Self = North;
+    pub const Bottom: Self = South;
+}
+
+impl Direction {
+    pub fn inverse(&self) -> Self {
+        match self {
+            North => South,
+            South => North,
+            East => West,
+            West => East,
+        }
+    }
+    pub fn turn_left(&self) -> Self {
+        match self {
+            North => West,
+            West => South,
+            South => East,
+            East => North,
+        }
+    }
+    pub fn turn_right(&self) -> Self {
+        match self {
+            North => East,
+            East => South,
+            South => West,
+            West => North,
+        }
+    }
+    /// The change in `(row, column)` of a single step in this direction.
+    pub fn delta(&self) -> (isize, isize) {
+        match self {
+            North => (-1, 0),
+            South => (1, 0),
+            East => (0, 1),
+            West => (0, -1),
+        }
+    }
+}
+
+/// Parse `"up"`, `"down"`, `"left"` or `"right"` (or the cardinal names),
+/// ignoring case.
+impl FromStr for Direction {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "up" | "north" => Ok(North),
+            "down" | "south" => Ok(South),
+            "right" | "east" => Ok(East),
+            "left" | "west" => Ok(West),
+            _ => Err(s.to_string()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Grid<T> {
     // Public within this crate since I may (ab)use these in multiple places.
@@ -46,17 +119,57 @@ impl<T> Grid<T> {
     pub fn shape(&self) -> (usize, usize) {
         (self.n_rows, self.n_cols)
     }
+    /// The element at `(i, j)`, or `None` if out of bounds.
+    #[inline]
+    pub fn get(&self, i: usize, j: usize) -> Option<&T> {
+        if i < self.n_rows && j < self.n_cols {
+            Some(&self.inner[self.linear_index(i, j)])
+        } else {
+            None
+        }
+    }
+    /// The element at `(i, j)`, or `None` if out of bounds.
+    #[inline]
+    pub fn get_mut(&mut self, i: usize, j: usize) -> Option<&mut T> {
+        if i < self.n_rows && j < self.n_cols {
+            let idx = self.linear_index(i, j);
+            Some(&mut self.inner[idx])
+        } else {
+            None
+        }
+    }
 
-    /// Transpose the square grid; panics if not square.
+    /// Transpose the grid in place. A square grid is transposed by swapping
+    /// across the diagonal; otherwise, the elements are permuted by following
+    /// the cycles of the transposition.
     pub fn transpose_mut(&mut self) {
         let n_rows = self.n_rows();
         let n_cols = self.n_cols();
-        // assert_eq!(n_rows, n_cols);
-        for j in 0..n_cols {
-            for i in 0..j {
-                let src = self.linear_index(i, j);
-                let dst = self.linear_index_tr(i, j);
-                self.inner.swap(src, dst);
+        if n_rows == n_cols {
+            for j in 0..n_cols {
+                for i in 0..j {
+                    let src = self.linear_index(i, j);
+                    let dst = self.linear_index_tr(i, j);
+                    self.inner.swap(src, dst);
+                }
+            }
+        } else {
+            // The element at `idx` belongs at `dst(idx)` in the transpose.
+            let dst = |idx: usize| {
+                let (i, j) = Self::cartesian_index(n_rows, idx);
+                j + n_cols * i
+            };
+            let mut visited = vec![false; self.len()];
+            for start in 0..self.len() {
+                if !visited[start] {
+                    visited[start] = true;
+                    let mut next = dst(start);
+                    while next != start {
+                        self.inner.swap(start, next);
+                        visited[next] = true;
+                        next = dst(next);
+                    }
+                }
             }
         }
         self.n_rows = n_cols;
@@ -70,6 +183,31 @@ impl<T> Grid<T> {
             n_cols,
         }
     }
+    /// Construct from row-major nested rows, which must be of equal length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self, String> {
+        let n_rows = rows.len();
+        let n_cols = rows.first().map_or(0, |row| row.len());
+        if let Some(i) = rows.iter().position(|row| row.len() != n_cols) {
+            return Err(format!(
+                "row {} has length {}, expected {}",
+                i,
+                rows[i].len(),
+                n_cols
+            ));
+        }
+        let mut iters: Vec<_> = rows.into_iter().map(|row| row.into_iter()).collect();
+        let mut inner = Vec::with_capacity(n_rows * n_cols);
+        for _ in 0..n_cols {
+            for iter in iters.iter_mut() {
+                inner.push(iter.next().unwrap());
+            }
+        }
+        Ok(Self {
+            inner,
+            n_rows,
+            n_cols,
+        })
+    }
 }
 
 impl<T: Clone> Grid<T> {
@@ -160,24 +298,145 @@ where
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut v = Vec::new();
         let mut n_rows: usize = 0;
-        for line in s.lines() {
+        let mut n_cols: usize = 0;
+        for (i, line) in s.lines().enumerate() {
             n_rows += 1;
+            let n = v.len();
             for c in line.chars() {
                 v.push(T::try_from(c)?);
             }
+            let len = v.len() - n;
+            if i == 0 {
+                n_cols = len;
+            } else if len != n_cols {
+                return Err(format!(
+                    "line {} has length {}, expected {}",
+                    i, len, n_cols
+                ));
+            }
         }
-        let n = v.len();
-        let n_cols = n / n_rows;
-        if n % n_rows != 0 {
-            Err(s.to_string())
-        } else {
-            let mut x = Grid::from_vec(v, n_cols, n_rows);
-            if n_rows == n_cols {
-                x.transpose_mut();
-                Ok(x)
-            } else {
-                Ok(x.transpose())
+        let mut x = Grid::from_vec(v, n_cols, n_rows);
+        x.transpose_mut();
+        Ok(x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn direction_aliases() {
+        assert_eq!(Direction::Up, North);
+        assert_eq!(Direction::Top, North);
+        assert_eq!(Direction::Down, South);
+        assert_eq!(Direction::Bottom, South);
+        assert_eq!(Direction::Left, West);
+        assert_eq!(Direction::Right, East);
+        let up = match South.inverse() {
+            Direction::Up => true,
+            Direction::Down | Direction::Left | Direction::Right => false,
+        };
+        assert!(up);
+    }
+
+    #[test]
+    fn direction_turn() {
+        assert_eq!(North.turn_left(), West);
+        assert_eq!(West.turn_left(), South);
+        assert_eq!(South.turn_left(), East);
+        assert_eq!(East.turn_left(), North);
+        assert_eq!(North.turn_right(), East);
+        assert_eq!(East.turn_right(), South);
+        assert_eq!(South.turn_right(), West);
+        assert_eq!(West.turn_right(), North);
+        for d in [North, South, East, West] {
+            assert_eq!(d.turn_right().turn_left(), d);
+            assert_eq!(d.turn_left().turn_right(), d);
+            assert_eq!(d.turn_left().turn_left(), d.inverse());
+            assert_eq!(d.inverse().inverse(), d);
+        }
+    }
+
+    #[test]
+    fn direction_from_str() {
+        assert_eq!("Up".parse::<Direction>(), Ok(North));
+        assert_eq!("DOWN".parse::<Direction>(), Ok(South));
+        assert_eq!("left".parse::<Direction>(), Ok(West));
+        assert_eq!("Right".parse::<Direction>(), Ok(East));
+        assert_eq!("west".parse::<Direction>(), Ok(West));
+        assert!("sideways".parse::<Direction>().is_err());
+        assert!("".parse::<Direction>().is_err());
+    }
+
+    #[test]
+    fn direction_delta() {
+        assert_eq!(North.delta(), (-1, 0));
+        assert_eq!(South.delta(), (1, 0));
+        assert_eq!(East.delta(), (0, 1));
+        assert_eq!(West.delta(), (0, -1));
+        for d in [North, South, East, West] {
+            let (di, dj) = d.delta();
+            let (ei, ej) = d.inverse().delta();
+            assert_eq!((di + ei, dj + ej), (0, 0));
+        }
+    }
+
+    #[test]
+    fn get() {
+        let mut x = Grid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap();
+        let (n_rows, n_cols) = x.shape();
+        assert_eq!(x.get(0, 0), Some(&1));
+        assert_eq!(x.get(1, 2), Some(&6));
+        assert_eq!(x.get(n_rows, 0), None);
+        assert_eq!(x.get(0, n_cols), None);
+        *x.get_mut(1, 0).unwrap() = 7;
+        assert_eq!(x[(1, 0)], 7);
+        assert_eq!(x.get_mut(n_rows, n_cols), None);
+    }
+
+    #[test]
+    fn transpose_mut() {
+        let mut x = Grid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap();
+        let rhs = Grid::from_rows(vec![vec![1, 4], vec![2, 5], vec![3, 6]]).unwrap();
+        assert_eq!(x.transpose(), rhs);
+        x.transpose_mut();
+        assert_eq!(x, rhs);
+        x.transpose_mut();
+        assert_eq!(
+            x,
+            Grid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap()
+        );
+
+        let rows: Vec<Vec<usize>> = (0..4)
+            .map(|i| (0..7).map(|j| 7 * i + j).collect())
+            .collect();
+        let mut x = Grid::from_rows(rows).unwrap();
+        let rhs = x.transpose();
+        x.transpose_mut();
+        assert_eq!(x, rhs);
+        for i in 0..7 {
+            for j in 0..4 {
+                assert_eq!(x[(i, j)], 7 * j + i);
             }
         }
+
+        let mut x = Grid::from_rows(vec![vec![1, 2], vec![3, 4]]).unwrap();
+        x.transpose_mut();
+        assert_eq!(x, Grid::from_rows(vec![vec![1, 3], vec![2, 4]]).unwrap());
+    }
+
+    #[test]
+    fn from_rows() {
+        let x = Grid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap();
+        assert_eq!(x.shape(), (2, 3));
+        assert_eq!(x[(0, 1)], 2);
+        assert_eq!(x[(1, 0)], 4);
+        assert_eq!(x.inner, vec![1, 4, 2, 5, 3, 6]);
+
+        assert!(Grid::from_rows(vec![vec![1, 2, 3], vec![4, 5]]).is_err());
+
+        let x = Grid::<u8>::from_rows(vec![]).unwrap();
+        assert_eq!(x.shape(), (0, 0));
     }
 }
@@ -18,3 +18,22 @@ pub mod day9;
 
 pub mod combinations;
 pub mod grid;
+
+#[cfg(test)]
+pub(crate) mod test_util {
+    /// A small, deterministic pseudo-random number generator (xorshift64).
+    pub(crate) struct XorShift(pub(crate) u64);
+    impl XorShift {
+        pub(crate) fn next(&mut self) -> u64 {
+            let mut x = self.0;
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            self.0 = x;
+            x
+        }
+        pub(crate) fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+    }
+}
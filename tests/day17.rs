@@ -0,0 +1,35 @@
+use std::fs;
+use std::process::Command;
+
+static TEST: &str = "\
+2413432311323
+3215453535623
+3255245654254
+3446585845452
+4546657867536
+1438598798454
+4457876987766
+3637877979653
+4654967986887
+4564679986453
+1224686865563
+2546548887735
+4322674655533";
+
+#[test]
+fn day17_bin() {
+    let path = temp_path("day17.txt");
+    fs::write(&path, TEST).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_day17"))
+        .arg(&path)
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "102\n94\n");
+}
+
+/// A path in the temporary directory which is unique to this process.
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("aoc-2023-{}-{}", std::process::id(), name))
+}